@@ -0,0 +1,3 @@
+ALTER TABLE transaction_stats ADD COLUMN IF NOT EXISTS market TEXT;
+
+CREATE INDEX IF NOT EXISTS transaction_stats_market_idx ON transaction_stats (market);
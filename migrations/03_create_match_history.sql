@@ -0,0 +1,36 @@
+ALTER TABLE transaction_stats ADD COLUMN IF NOT EXISTS tx_id TEXT;
+
+CREATE TABLE IF NOT EXISTS match_transactions (
+    id BIGSERIAL PRIMARY KEY,
+    tx_id TEXT NOT NULL UNIQUE,
+    market TEXT NOT NULL,
+    status TEXT NOT NULL,
+    wallet TEXT NOT NULL,
+    gas_used BIGINT,
+    block_height BIGINT,
+    submitted_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS match_transactions_wallet_idx ON match_transactions (wallet);
+CREATE INDEX IF NOT EXISTS match_transactions_submitted_at_idx ON match_transactions (submitted_at);
+
+CREATE TABLE IF NOT EXISTS matched_trades (
+    id BIGSERIAL PRIMARY KEY,
+    tx_id TEXT NOT NULL REFERENCES match_transactions (tx_id),
+    market TEXT NOT NULL,
+    buy_order_id TEXT NOT NULL,
+    sell_order_id TEXT NOT NULL,
+    buy_user TEXT NOT NULL,
+    sell_user TEXT NOT NULL,
+    price NUMERIC(39, 0) NOT NULL,
+    amount NUMERIC(39, 0) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS matched_trades_tx_id_idx ON matched_trades (tx_id);
+CREATE INDEX IF NOT EXISTS matched_trades_buy_order_idx ON matched_trades (buy_order_id);
+CREATE INDEX IF NOT EXISTS matched_trades_sell_order_idx ON matched_trades (sell_order_id);
+CREATE INDEX IF NOT EXISTS matched_trades_buy_user_idx ON matched_trades (buy_user);
+CREATE INDEX IF NOT EXISTS matched_trades_sell_user_idx ON matched_trades (sell_user);
+CREATE INDEX IF NOT EXISTS matched_trades_created_at_idx ON matched_trades (created_at);
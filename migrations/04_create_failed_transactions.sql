@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS failed_transactions (
+    id BIGSERIAL PRIMARY KEY,
+    market TEXT NOT NULL,
+    wallet TEXT NOT NULL,
+    order_ids TEXT[] NOT NULL,
+    reason TEXT NOT NULL,
+    revert_code TEXT,
+    error TEXT NOT NULL,
+    submitted_at TIMESTAMPTZ NOT NULL,
+    failed_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS failed_transactions_failed_at_idx ON failed_transactions (failed_at);
+CREATE INDEX IF NOT EXISTS failed_transactions_market_reason_idx ON failed_transactions (market, reason);
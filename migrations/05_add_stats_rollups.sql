@@ -0,0 +1,23 @@
+-- created_at is when the transaction completed; recorded_at is when the row was written, so rows
+-- replayed late from the spill file are still picked up by the rollup.
+ALTER TABLE transaction_stats ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE transaction_stats ADD COLUMN IF NOT EXISTS recorded_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+CREATE INDEX IF NOT EXISTS transaction_stats_created_at_idx ON transaction_stats (created_at);
+CREATE INDEX IF NOT EXISTS transaction_stats_recorded_at_idx ON transaction_stats (recorded_at);
+
+CREATE TABLE IF NOT EXISTS transaction_stats_rollup (
+    bucket TEXT NOT NULL,
+    bucket_start TIMESTAMPTZ NOT NULL,
+    market TEXT NOT NULL,
+    transactions BIGINT NOT NULL,
+    matches BIGINT NOT NULL,
+    volume NUMERIC NOT NULL,
+    gas_used BIGINT NOT NULL,
+    match_time_ms BIGINT NOT NULL,
+    receive_time_ms BIGINT NOT NULL,
+    post_time_ms BIGINT NOT NULL,
+    buy_orders BIGINT NOT NULL,
+    sell_orders BIGINT NOT NULL,
+    PRIMARY KEY (bucket, bucket_start, market)
+);
@@ -0,0 +1,7 @@
+ALTER TABLE transaction_stats
+    ALTER COLUMN total_transactions TYPE BIGINT,
+    ALTER COLUMN total_amount TYPE NUMERIC(39, 0) USING NULLIF(total_amount, '')::NUMERIC(39, 0),
+    ALTER COLUMN avg_gas_used TYPE NUMERIC,
+    ALTER COLUMN total_gas_used TYPE BIGINT,
+    ALTER COLUMN buy_orders TYPE BIGINT,
+    ALTER COLUMN sell_orders TYPE BIGINT;
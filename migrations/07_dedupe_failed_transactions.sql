@@ -0,0 +1,3 @@
+-- Lets the transaction log writer replay spilled failures without duplicating them.
+CREATE UNIQUE INDEX IF NOT EXISTS failed_transactions_wallet_submitted_at_idx
+    ON failed_transactions (wallet, submitted_at);
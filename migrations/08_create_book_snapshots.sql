@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS book_snapshots (
+    market TEXT PRIMARY KEY,
+    taken_at TIMESTAMPTZ NOT NULL,
+    snapshot JSONB NOT NULL
+);
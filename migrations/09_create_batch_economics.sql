@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS batch_economics (
+    id BIGSERIAL PRIMARY KEY,
+    market TEXT NOT NULL,
+    decided_at TIMESTAMPTZ NOT NULL,
+    decision TEXT NOT NULL,
+    matches BIGINT NOT NULL,
+    orders BIGINT NOT NULL,
+    estimated_gas BIGINT NOT NULL,
+    gas_price BIGINT NOT NULL,
+    -- estimated_fee is in the chain's gas asset; estimated_fee_quote and expected_revenue are in
+    -- the market's quote asset.
+    estimated_fee NUMERIC(39, 0) NOT NULL,
+    estimated_fee_quote NUMERIC(39, 0) NOT NULL,
+    expected_revenue NUMERIC(39, 0) NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS batch_economics_market_decided_at_idx
+    ON batch_economics (market, decided_at);
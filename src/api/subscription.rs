@@ -5,9 +5,8 @@ use crate::model::OrderType;
 
 use log::info;
 
-pub fn format_graphql_subscription(order_type: OrderType) -> String {
+pub fn format_graphql_subscription(order_type: OrderType, market: &str) -> String {
     let limit = ev("FETCH_ORDER_LIMIT").unwrap_or_default();
-    let market = ev("CONTRACT_ID").unwrap_or_default();  
     let order_type_str = match order_type {
         OrderType::Sell => "ActiveSellOrder",
         OrderType::Buy => "ActiveBuyOrder",
@@ -17,11 +16,13 @@ pub fn format_graphql_subscription(order_type: OrderType) -> String {
         r#"query MyQuery {{
             {}(limit: {}, where: {{market: {{_eq: "{}"}}}}) {{
                 id
+                market
                 user
                 timestamp
                 order_type
                 amount
                 asset
+                asset_type
                 price
                 status
                 db_write_timestamp
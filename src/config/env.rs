@@ -1,7 +1,22 @@
 use std::env;
+use std::str::FromStr;
 
 use crate::error::Error;
 
 pub fn ev(key: &str) -> Result<String, Error> {
     env::var(key).map_err(Error::EnvVarError)
 }
+
+pub fn ev_or<T: FromStr>(key: &str, default: T) -> T {
+    ev(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+/// Per-market setting: `MARKET_<index>_<KEY>`, falling back to the global `<KEY>`.
+pub fn market_ev(index: usize, key: &str) -> Result<String, Error> {
+    ev(&format!("MARKET_{}_{}", index, key)).or_else(|_| ev(key))
+}
+
+/// Like [`market_ev`], with a default for unset or unparsable values.
+pub fn market_ev_or<T: FromStr>(index: usize, key: &str, default: T) -> T {
+    ev_or(&format!("MARKET_{}_{}", index, key), ev_or(key, default))
+}
@@ -1,3 +1,3 @@
 pub mod env;
 
-pub use env::ev;
+pub use env::{ev, ev_or, market_ev, market_ev_or};
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use log::info;
+use sqlx::migrate::Migrator;
+use sqlx::PgPool;
+
+use crate::error::Error;
+
+/// Migrations from `migrations/`, embedded at compile time.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Applies pending migrations. Replicas starting together serialize on the
+/// advisory lock sqlx takes while migrating.
+pub async fn migrate(db_pool: &PgPool) -> Result<(), Error> {
+    MIGRATOR.run(db_pool).await?;
+    info!("Database schema is up to date");
+    Ok(())
+}
+
+/// Refuses to start unless the applied migrations are exactly the embedded
+/// ones: none pending, none unknown to this build, no edited or failed ones.
+pub async fn check_schema(db_pool: &PgPool) -> Result<(), Error> {
+    let table: Option<String> = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations')::TEXT")
+        .fetch_one(db_pool)
+        .await?;
+    let applied: Vec<(i64, Vec<u8>, bool)> = if table.is_some() {
+        sqlx::query_as("SELECT version, checksum, success FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(db_pool)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    let embedded: HashMap<i64, &[u8]> = MIGRATOR
+        .iter()
+        .map(|m| (m.version, m.checksum.as_ref()))
+        .collect();
+    let mut problems = Vec::new();
+
+    for (version, checksum, success) in &applied {
+        match embedded.get(version) {
+            None => problems.push(format!("migration {} is not known to this build", version)),
+            Some(expected) if *expected != checksum.as_slice() => problems.push(format!(
+                "migration {} was modified after it was applied",
+                version
+            )),
+            _ if !success => problems.push(format!("migration {} did not complete", version)),
+            _ => {}
+        }
+    }
+    for migration in MIGRATOR.iter() {
+        if !applied
+            .iter()
+            .any(|(version, _, _)| *version == migration.version)
+        {
+            problems.push(format!(
+                "migration {} ({}) has not been applied",
+                migration.version, migration.description
+            ));
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::SchemaDriftError(problems.join("; ")))
+    }
+}
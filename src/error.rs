@@ -3,9 +3,10 @@ use std::env::VarError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     #[error("Failed to connect to WebSocket")]
-    WebSocketConnectionError(#[from] tokio_tungstenite::tungstenite::Error),
+    WebSocketConnectionError(#[source] Box<tokio_tungstenite::tungstenite::Error>),
 
     #[error("Fuel error: {0}")]
     FuelError(#[from] fuels::types::errors::Error),
@@ -17,7 +18,7 @@ pub enum Error {
     UrlParseError(#[from] url::ParseError),
 
     #[error("Rocket  error {0}")]
-    RocketError(#[from] rocket::Error),
+    RocketError(Box<rocket::Error>),
 
     #[error("Failed to parse from hex")]
     FromHexParseError(#[from] hex::FromHexError),
@@ -31,17 +32,48 @@ pub enum Error {
     #[error("Failed to match orders: {0}")]
     MatchOrdersError(String),
 
-    #[error("Failed to parse order amount: {0}")]
-    OrderAmountParseError(String),
-
     #[error("Failed to parse contract ID")]
     ContractIdParseError(#[from] std::num::ParseIntError),
 
-    #[error("Failed to parse contract ID")]
-    ProcessMessagePayloadError(String),
-
     #[error("String parsing error: {0}")]
     StringParsingError(String),
+
+    #[error("Wallet error: {0}")]
+    WalletError(String),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Task error: {0}")]
+    TaskError(String),
+
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+
+    #[error("Migration error: {0}")]
+    MigrationError(#[from] sqlx::migrate::MigrateError),
+
+    #[error("Value out of range: {0}")]
+    ValueOverflowError(String),
+
+    #[error("Database schema does not match this build: {0}")]
+    SchemaDriftError(String),
+
+    #[error("Market metadata error: {0}")]
+    MarketMetadataError(String),
+}
+
+// The large errors are boxed to keep `Result<_, Error>` small.
+impl From<tokio_tungstenite::tungstenite::Error> for Error {
+    fn from(e: tokio_tungstenite::tungstenite::Error) -> Self {
+        Error::WebSocketConnectionError(Box::new(e))
+    }
+}
+
+impl From<rocket::Error> for Error {
+    fn from(e: rocket::Error) -> Self {
+        Error::RocketError(Box::new(e))
+    }
 }
 
 impl From<&str> for Error {
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::model::OrderType;
+
+/// Real-time updates pushed to clients of the event stream.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketEvent {
+    /// A price level changed; `order_count == 0` means the level is gone.
+    BookLevel {
+        market: String,
+        side: OrderType,
+        price: u128,
+        total_amount: u128,
+        order_count: usize,
+    },
+    MatchFound {
+        market: String,
+        buy_id: String,
+        sell_id: String,
+        amount: u128,
+    },
+    TransactionSubmitted {
+        market: String,
+        wallet: String,
+        order_ids: Vec<String>,
+    },
+    TransactionConfirmed {
+        market: String,
+        tx_id: String,
+        gas_used: u64,
+    },
+    TransactionFailed {
+        market: String,
+        order_ids: Vec<String>,
+        error: String,
+    },
+}
+
+impl MarketEvent {
+    pub fn market(&self) -> &str {
+        match self {
+            MarketEvent::BookLevel { market, .. }
+            | MarketEvent::MatchFound { market, .. }
+            | MarketEvent::TransactionSubmitted { market, .. }
+            | MarketEvent::TransactionConfirmed { market, .. }
+            | MarketEvent::TransactionFailed { market, .. } => market,
+        }
+    }
+
+    /// Event name used for the SSE `event:` field.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MarketEvent::BookLevel { .. } => "book_level",
+            MarketEvent::MatchFound { .. } => "match_found",
+            MarketEvent::TransactionSubmitted { .. } => "transaction_submitted",
+            MarketEvent::TransactionConfirmed { .. } => "transaction_confirmed",
+            MarketEvent::TransactionFailed { .. } => "transaction_failed",
+        }
+    }
+}
+
+/// Broadcast channel fed by the order books and matchers. Slow subscribers
+/// lose the oldest events instead of holding up the publishers.
+pub struct EventBus {
+    sender: broadcast::Sender<MarketEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Arc<Self> {
+        let (sender, _) = broadcast::channel(capacity);
+        Arc::new(Self { sender })
+    }
+
+    pub fn publish(&self, event: MarketEvent) {
+        // An error only means nobody is subscribed right now.
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<MarketEvent> {
+        self.sender.subscribe()
+    }
+}
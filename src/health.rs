@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+
+/// Result of the most recent `match_order_many` submission.
+#[derive(Debug, Clone)]
+pub struct TransactionOutcome {
+    pub market: String,
+    pub tx_id: Option<String>,
+    pub error: Option<String>,
+    pub at: Instant,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TransactionOutcomeReport {
+    pub market: String,
+    pub success: bool,
+    pub tx_id: Option<String>,
+    pub error: Option<String>,
+    pub secs_ago: f64,
+}
+
+/// Liveness signals recorded by the indexer client and the matchers, read
+/// by the health endpoints.
+pub struct HealthState {
+    indexer_connected: AtomicBool,
+    last_data_frame: RwLock<Option<Instant>>,
+    last_cycles: RwLock<HashMap<String, Instant>>,
+    last_transaction: RwLock<Option<TransactionOutcome>>,
+}
+
+impl HealthState {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            indexer_connected: AtomicBool::new(false),
+            last_data_frame: RwLock::new(None),
+            last_cycles: RwLock::new(HashMap::new()),
+            last_transaction: RwLock::new(None),
+        })
+    }
+
+    pub fn set_indexer_connected(&self, connected: bool) {
+        self.indexer_connected.store(connected, Ordering::Relaxed);
+    }
+
+    pub fn indexer_connected(&self) -> bool {
+        self.indexer_connected.load(Ordering::Relaxed)
+    }
+
+    pub async fn record_data_frame(&self) {
+        *self.last_data_frame.write().await = Some(Instant::now());
+    }
+
+    pub async fn secs_since_data_frame(&self) -> Option<f64> {
+        self.last_data_frame
+            .read()
+            .await
+            .map(|at| at.elapsed().as_secs_f64())
+    }
+
+    pub async fn record_cycle(&self, market: &str) {
+        self.last_cycles
+            .write()
+            .await
+            .insert(market.to_string(), Instant::now());
+    }
+
+    /// Seconds since the last matching cycle, per market.
+    pub async fn cycle_ages(&self) -> HashMap<String, f64> {
+        self.last_cycles
+            .read()
+            .await
+            .iter()
+            .map(|(market, at)| (market.clone(), at.elapsed().as_secs_f64()))
+            .collect()
+    }
+
+    pub async fn record_transaction(
+        &self,
+        market: &str,
+        tx_id: Option<String>,
+        error: Option<String>,
+    ) {
+        *self.last_transaction.write().await = Some(TransactionOutcome {
+            market: market.to_string(),
+            tx_id,
+            error,
+            at: Instant::now(),
+        });
+    }
+
+    pub async fn last_transaction(&self) -> Option<TransactionOutcomeReport> {
+        self.last_transaction
+            .read()
+            .await
+            .as_ref()
+            .map(|outcome| TransactionOutcomeReport {
+                market: outcome.market.clone(),
+                success: outcome.error.is_none(),
+                tx_id: outcome.tx_id.clone(),
+                error: outcome.error.clone(),
+                secs_ago: outcome.at.elapsed().as_secs_f64(),
+            })
+    }
+}
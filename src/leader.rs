@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use log::{error, info, warn};
+use sqlx::{Connection, PgConnection};
+use tokio::sync::watch;
+use tokio::time::{self, Duration};
+
+use crate::config::{ev, ev_or};
+use crate::error::Error;
+use crate::util::Shutdown;
+
+/// Leader election between matcher replicas through a Postgres session-level
+/// advisory lock. Only the replica holding the lock submits transactions;
+/// the lock is released by Postgres as soon as the leader's session ends.
+pub struct LeaderElection {
+    database_url: String,
+    lock_key: i64,
+    retry_interval: Duration,
+    sender: watch::Sender<bool>,
+    receiver: watch::Receiver<bool>,
+}
+
+impl LeaderElection {
+    /// Returns `None` when `LEADER_ELECTION` is disabled, in which case the
+    /// process always acts as leader.
+    pub fn from_env() -> Result<Option<Arc<Self>>, Error> {
+        if !ev_or("LEADER_ELECTION", false) {
+            return Ok(None);
+        }
+        let (sender, receiver) = watch::channel(false);
+        Ok(Some(Arc::new(Self {
+            database_url: ev("DATABASE_URL")?,
+            lock_key: ev_or("LEADER_LOCK_KEY", 0x5350_4b4d),
+            retry_interval: Duration::from_secs(ev_or("LEADER_RETRY_SECS", 2)),
+            sender,
+            receiver,
+        })))
+    }
+
+    pub fn is_leader(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Resolves once this replica holds the lock.
+    pub async fn wait_until_leader(&self) {
+        let mut receiver = self.receiver.clone();
+        while !*receiver.borrow_and_update() {
+            if receiver.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    fn set_leader(&self, leader: bool) {
+        if self.sender.send_replace(leader) != leader {
+            if leader {
+                info!("Acquired leader lock {}, matching enabled", self.lock_key);
+            } else {
+                warn!("Lost leader lock {}, matching paused", self.lock_key);
+            }
+        }
+    }
+
+    /// Keeps trying to take the lock and, while holding it, checks that the
+    /// session is still alive every `retry_interval`. The lock is held until
+    /// `release` is triggered, which should happen only after in-flight
+    /// transactions have been drained.
+    pub async fn run(&self, release: Shutdown) -> Result<(), Error> {
+        while !release.is_triggered() {
+            let mut conn = match PgConnection::connect(&self.database_url).await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    error!("Leader election connection failed: {:?}", e);
+                    self.set_leader(false);
+                    self.sleep(&release).await;
+                    continue;
+                }
+            };
+
+            while !release.is_triggered() {
+                let check = if self.is_leader() {
+                    sqlx::query("SELECT 1")
+                        .execute(&mut conn)
+                        .await
+                        .map(|_| true)
+                } else {
+                    sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_lock($1)")
+                        .bind(self.lock_key)
+                        .fetch_one(&mut conn)
+                        .await
+                };
+                match check {
+                    Ok(leader) => self.set_leader(leader),
+                    Err(e) => {
+                        error!("Leader election session failed: {:?}", e);
+                        self.set_leader(false);
+                        break;
+                    }
+                }
+                self.sleep(&release).await;
+            }
+
+            if release.is_triggered() && self.is_leader() {
+                let _ = sqlx::query("SELECT pg_advisory_unlock($1)")
+                    .bind(self.lock_key)
+                    .execute(&mut conn)
+                    .await;
+                self.set_leader(false);
+            }
+        }
+        Ok(())
+    }
+
+    async fn sleep(&self, release: &Shutdown) {
+        tokio::select! {
+            _ = time::sleep(self.retry_interval) => {}
+            _ = release.wait() => {}
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use sqlx::types::BigDecimal;
+use sqlx::{PgPool, Postgres, Transaction};
+
+use super::{BatchEconomicsLog, FailedTransactionLog, LogEntry, TransactionLog, TransactionStatus};
+use crate::error::Error;
+use crate::util::decimal::numeric;
+
+/// `transaction_stats` values converted to their column types.
+struct StatsRow {
+    total_amount: BigDecimal,
+    matches_len: i64,
+    buy_orders: i64,
+    sell_orders: i64,
+    total_gas_used: i64,
+    avg_gas_used: BigDecimal,
+}
+
+impl TransactionLog {
+    fn stats_row(&self) -> Result<StatsRow, Error> {
+        let matches_len: i64 = exact(self.matches_len, "matches_len")?;
+        let total_gas_used: i64 = exact(self.gas_used, "gas_used")?;
+        Ok(StatsRow {
+            total_amount: numeric(self.total_amount),
+            matches_len,
+            buy_orders: exact(self.buy_orders, "buy_orders")?,
+            sell_orders: exact(self.sell_orders, "sell_orders")?,
+            total_gas_used,
+            avg_gas_used: (BigDecimal::from(total_gas_used) / BigDecimal::from(matches_len.max(1)))
+                .with_scale(2),
+        })
+    }
+}
+
+impl LogEntry {
+    /// Fails for entries that can never be written, so they are set aside
+    /// instead of being retried.
+    pub fn check(&self) -> Result<(), Error> {
+        match self {
+            LogEntry::Confirmed(log) => log.stats_row().map(|_| ()),
+            LogEntry::Failed(log) => log.check(),
+            LogEntry::Economics(log) => log.check(),
+        }
+    }
+}
+
+/// Writes a batch in one database transaction. Entries that are already
+/// stored are skipped, so a batch can safely be written again.
+pub async fn write_batch(db_pool: &PgPool, entries: &[LogEntry]) -> Result<(), Error> {
+    let mut tx = db_pool.begin().await?;
+    for entry in entries {
+        match entry {
+            LogEntry::Confirmed(log) => write_log(&mut tx, log).await?,
+            LogEntry::Failed(log) => write_failure(&mut tx, log).await?,
+            LogEntry::Economics(log) => write_economics(&mut tx, log).await?,
+        }
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Stores the transaction, its individual trades and the aggregate stats row.
+async fn write_log(tx: &mut Transaction<'_, Postgres>, log: &TransactionLog) -> Result<(), Error> {
+    let row = log.stats_row()?;
+
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO match_transactions (tx_id, market, status, wallet, gas_used, block_height, submitted_at, completed_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (tx_id) DO NOTHING
+        "#,
+        log.tx_id,
+        log.market,
+        TransactionStatus::Confirmed.as_str(),
+        log.wallet,
+        row.total_gas_used,
+        log.block_height.map(i64::from),
+        log.submitted_at,
+        log.completed_at
+    )
+    .execute(&mut *tx)
+    .await?;
+    if inserted.rows_affected() == 0 {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO transaction_stats (market, tx_id, total_transactions, total_amount, avg_gas_used, total_gas_used, match_time_ms, buy_orders, sell_orders, receive_time_ms, post_time_ms, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        "#,
+        log.market,
+        log.tx_id,
+        row.matches_len,
+        row.total_amount,
+        row.avg_gas_used,
+        row.total_gas_used,
+        log.match_time_ms,
+        row.buy_orders,
+        row.sell_orders,
+        log.receive_time_ms,
+        log.post_time_ms,
+        log.completed_at
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    for trade in &log.trades {
+        sqlx::query!(
+            r#"
+            INSERT INTO matched_trades (tx_id, market, buy_order_id, sell_order_id, buy_user, sell_user, price, amount, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            log.tx_id,
+            log.market,
+            trade.buy_order_id,
+            trade.sell_order_id,
+            trade.buy_user,
+            trade.sell_user,
+            numeric(trade.price),
+            numeric(trade.amount),
+            log.completed_at
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    Ok(())
+}
+
+impl FailedTransactionLog {
+    fn check(&self) -> Result<(), Error> {
+        if let Some(gas_used) = self.transaction.as_ref().and_then(|t| t.gas_used) {
+            exact::<_, i64>(gas_used, "gas_used")?;
+        }
+        Ok(())
+    }
+}
+
+/// Stores the failure and, when the node accepted the transaction, its
+/// history row with the status it ended in.
+async fn write_failure(
+    tx: &mut Transaction<'_, Postgres>,
+    log: &FailedTransactionLog,
+) -> Result<(), Error> {
+    if let Some(sent) = &log.transaction {
+        let gas_used = sent
+            .gas_used
+            .map(|gas| exact::<_, i64>(gas, "gas_used"))
+            .transpose()?;
+        sqlx::query!(
+            r#"
+            INSERT INTO match_transactions (tx_id, market, status, wallet, gas_used, block_height, submitted_at, completed_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (tx_id) DO NOTHING
+            "#,
+            sent.tx_id,
+            log.market,
+            sent.status.as_str(),
+            log.wallet,
+            gas_used,
+            sent.block_height.map(i64::from),
+            log.submitted_at,
+            log.failed_at
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO failed_transactions (market, wallet, order_ids, reason, revert_code, error, submitted_at, failed_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (wallet, submitted_at) DO NOTHING
+        "#,
+        log.market,
+        log.wallet,
+        &log.order_ids,
+        log.reason.as_str(),
+        log.revert_code,
+        log.error,
+        log.submitted_at,
+        log.failed_at
+    )
+    .execute(&mut *tx)
+    .await?;
+    Ok(())
+}
+
+impl BatchEconomicsLog {
+    fn check(&self) -> Result<(), Error> {
+        exact::<_, i64>(self.matches, "matches")?;
+        exact::<_, i64>(self.orders, "orders")?;
+        exact::<_, i64>(self.estimated_gas, "estimated_gas")?;
+        exact::<_, i64>(self.gas_price, "gas_price")?;
+        Ok(())
+    }
+}
+
+async fn write_economics(
+    tx: &mut Transaction<'_, Postgres>,
+    log: &BatchEconomicsLog,
+) -> Result<(), Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO batch_economics (market, decided_at, decision, matches, orders, estimated_gas, gas_price, estimated_fee, estimated_fee_quote, expected_revenue)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        ON CONFLICT (market, decided_at) DO NOTHING
+        "#,
+        log.market,
+        log.decided_at,
+        log.decision,
+        exact::<_, i64>(log.matches, "matches")?,
+        exact::<_, i64>(log.orders, "orders")?,
+        exact::<_, i64>(log.estimated_gas, "estimated_gas")?,
+        exact::<_, i64>(log.gas_price, "gas_price")?,
+        numeric(log.estimated_fee),
+        numeric(log.estimated_fee_quote),
+        numeric(log.expected_revenue)
+    )
+    .execute(&mut *tx)
+    .await?;
+    Ok(())
+}
+
+/// Converts to a database integer type, failing instead of wrapping.
+fn exact<T, U>(value: T, field: &str) -> Result<U, Error>
+where
+    T: Copy + std::fmt::Display,
+    U: TryFrom<T>,
+{
+    U::try_from(value).map_err(|_| {
+        Error::ValueOverflowError(format!("{} = {} does not fit the column", field, value))
+    })
+}
@@ -0,0 +1,441 @@
+mod db;
+mod spill;
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr, PickFirst};
+use sqlx::PgPool;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task;
+use tokio::time::{self, Duration};
+
+use crate::config::ev_or;
+use crate::error::Error;
+use crate::market::failure::FailureReason;
+use crate::model::MatchedTrade;
+pub use spill::SpillFile;
+
+/// Records queued for the database by the matchers.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LogEntry {
+    Confirmed(TransactionLog),
+    Failed(FailedTransactionLog),
+    Economics(BatchEconomicsLog),
+}
+
+/// `u128` fields are written as strings: serde buffers internally tagged
+/// enums and cannot read back numbers above `u64::MAX`. Numbers are still
+/// accepted for entries spilled before.
+#[serde_as]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionLog {
+    pub market: String,
+    pub wallet: String,
+    #[serde_as(as = "PickFirst<(DisplayFromStr, _)>")]
+    pub total_amount: u128,
+    pub matches_len: usize,
+    pub tx_id: String,
+    pub gas_used: u64,
+    pub match_time_ms: i64,
+    pub buy_orders: usize,
+    pub sell_orders: usize,
+    pub receive_time_ms: i64,
+    pub post_time_ms: i64,
+    pub submitted_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    pub block_height: Option<u32>,
+    pub trades: Vec<MatchedTrade>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FailedTransactionLog {
+    pub market: String,
+    pub wallet: String,
+    pub order_ids: Vec<String>,
+    pub reason: FailureReason,
+    pub revert_code: Option<String>,
+    pub error: String,
+    pub submitted_at: DateTime<Utc>,
+    pub failed_at: DateTime<Utc>,
+    /// Set when the node accepted the transaction, which is then also stored
+    /// in `match_transactions`. Missing in entries spilled before.
+    #[serde(default)]
+    pub transaction: Option<SentTransaction>,
+}
+
+/// `match_transactions.status` of a transaction the node accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    Confirmed,
+    Reverted,
+    /// Dropped from the pool before it made it into a block.
+    SqueezedOut,
+}
+
+impl TransactionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionStatus::Confirmed => "confirmed",
+            TransactionStatus::Reverted => "reverted",
+            TransactionStatus::SqueezedOut => "squeezed_out",
+        }
+    }
+}
+
+/// A failed transaction that got a tx id from the node.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SentTransaction {
+    pub tx_id: String,
+    pub status: TransactionStatus,
+    pub gas_used: Option<u64>,
+    pub block_height: Option<u32>,
+}
+
+/// Fee estimate and profitability decision for one candidate batch.
+#[serde_as]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchEconomicsLog {
+    pub market: String,
+    pub decided_at: DateTime<Utc>,
+    pub decision: String,
+    pub matches: usize,
+    pub orders: usize,
+    pub estimated_gas: u64,
+    pub gas_price: u64,
+    /// In the gas asset.
+    #[serde_as(as = "PickFirst<(DisplayFromStr, _)>")]
+    pub estimated_fee: u128,
+    /// `estimated_fee` in the quote asset.
+    #[serde_as(as = "PickFirst<(DisplayFromStr, _)>")]
+    pub estimated_fee_quote: u128,
+    /// Matcher fees in the quote asset.
+    #[serde_as(as = "PickFirst<(DisplayFromStr, _)>")]
+    pub expected_revenue: u128,
+}
+
+/// Sending half of the transaction log. Never blocks or fails: when the
+/// queue is full or the writer is gone, entries are appended to the spill
+/// file on the blocking pool.
+#[derive(Clone)]
+pub struct LogQueue {
+    sender: mpsc::Sender<LogEntry>,
+    spill: Arc<SpillFile>,
+}
+
+impl LogQueue {
+    pub fn push(&self, entry: LogEntry) {
+        match self.sender.try_send(entry) {
+            Ok(()) => {}
+            Err(TrySendError::Full(entry)) | Err(TrySendError::Closed(entry)) => {
+                warn!(
+                    "Transaction log queue unavailable, spilling to {:?}",
+                    self.spill.path()
+                );
+                // Appending fsyncs the file, which must not stall the
+                // matcher's worker thread.
+                let spill = self.spill.clone();
+                task::spawn_blocking(move || spill.append(&[entry]));
+            }
+        }
+    }
+}
+
+/// Writes queued entries to Postgres in batches, retrying with backoff.
+/// Batches that still fail are spilled to a local file, which is replayed
+/// once the database accepts writes again.
+pub struct TransactionLogWriter {
+    receiver: Mutex<mpsc::Receiver<LogEntry>>,
+    db_pool: PgPool,
+    spill: Arc<SpillFile>,
+    batch_size: usize,
+    max_retries: u32,
+    replay_interval: Duration,
+}
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Creates the bounded log queue and its writer from the environment.
+pub fn channel(db_pool: PgPool) -> (LogQueue, TransactionLogWriter) {
+    let (sender, receiver) = mpsc::channel(ev_or("TRANSACTION_LOG_QUEUE_SIZE", 10_000));
+    let spill = Arc::new(SpillFile::new(ev_or(
+        "TRANSACTION_LOG_SPILL_FILE",
+        "transaction-log.spill.jsonl".to_string(),
+    )));
+    let writer = TransactionLogWriter {
+        receiver: Mutex::new(receiver),
+        db_pool,
+        spill: spill.clone(),
+        batch_size: ev_or("TRANSACTION_LOG_BATCH_SIZE", 100usize).max(1),
+        max_retries: ev_or("TRANSACTION_LOG_MAX_RETRIES", 5),
+        replay_interval: Duration::from_secs(ev_or("TRANSACTION_LOG_REPLAY_SECS", 30)),
+    };
+    (LogQueue { sender, spill }, writer)
+}
+
+impl TransactionLogWriter {
+    /// Runs until every `LogQueue` is dropped and the queue is drained. The
+    /// receiver is shared so the task can be restarted by the supervisor.
+    pub async fn run(&self) -> Result<(), Error> {
+        let mut receiver = self.receiver.lock().await;
+        let mut replay_ticker = time::interval(self.replay_interval);
+
+        loop {
+            let entry = tokio::select! {
+                entry = receiver.recv() => match entry {
+                    Some(entry) => entry,
+                    None => break,
+                },
+                _ = replay_ticker.tick() => {
+                    self.replay().await;
+                    continue;
+                }
+            };
+
+            let mut batch = vec![entry];
+            while batch.len() < self.batch_size {
+                match receiver.try_recv() {
+                    Ok(entry) => batch.push(entry),
+                    Err(_) => break,
+                }
+            }
+            self.write(batch).await;
+        }
+
+        self.replay().await;
+        info!("Transaction log channel closed, all queued transactions written");
+        Ok(())
+    }
+
+    /// Moves entries still queued to the spill file so they are replayed on
+    /// the next start. Called after `run` was aborted on shutdown; later
+    /// pushes go straight to the spill file as the queue is closed.
+    pub async fn spill_queued(&self) {
+        let mut receiver = self.receiver.lock().await;
+        receiver.close();
+        let mut entries = Vec::new();
+        while let Ok(entry) = receiver.try_recv() {
+            entries.push(entry);
+        }
+        if !entries.is_empty() {
+            warn!(
+                "Spilling {} unwritten transaction log entries to {:?}",
+                entries.len(),
+                self.spill.path()
+            );
+            self.spill.append(&entries);
+        }
+    }
+
+    async fn write(&self, batch: Vec<LogEntry>) {
+        let batch: Vec<LogEntry> = batch
+            .into_iter()
+            .filter(|entry| match entry.check() {
+                Ok(()) => true,
+                Err(e) => {
+                    self.reject(entry, &e);
+                    false
+                }
+            })
+            .collect();
+        if batch.is_empty() {
+            return;
+        }
+
+        let mut backoff = INITIAL_BACKOFF;
+        for attempt in 0..=self.max_retries {
+            match db::write_batch(&self.db_pool, &batch).await {
+                Ok(()) => {
+                    if self.spill.has_pending() {
+                        self.replay().await;
+                    }
+                    return;
+                }
+                Err(e) if attempt < self.max_retries => {
+                    warn!(
+                        "Failed to write {} transaction log entries (attempt {}), retrying in {:?}: {:?}",
+                        batch.len(),
+                        attempt + 1,
+                        backoff,
+                        e
+                    );
+                    time::sleep(backoff).await;
+                    backoff = std::cmp::min(backoff * 2, MAX_BACKOFF);
+                }
+                Err(e) => error!(
+                    "Giving up on writing {} transaction log entries: {:?}",
+                    batch.len(),
+                    e
+                ),
+            }
+        }
+        self.spill.append(&batch);
+    }
+
+    /// Writes spilled entries back, one batch at a time, stopping at the
+    /// first failure and keeping the rest for the next attempt.
+    async fn replay(&self) {
+        let lines = match self.spill.take() {
+            Ok(lines) if lines.is_empty() => return,
+            Ok(lines) => lines,
+            Err(e) => {
+                error!("Failed to read spilled transaction logs: {:?}", e);
+                return;
+            }
+        };
+        info!("Replaying {} spilled transaction log entries", lines.len());
+
+        let mut written = 0;
+        for chunk in lines.chunks(self.batch_size) {
+            let mut entries = Vec::with_capacity(chunk.len());
+            for line in chunk {
+                match serde_json::from_str::<LogEntry>(line) {
+                    Ok(entry) => match entry.check() {
+                        Ok(()) => entries.push(entry),
+                        Err(e) => self.reject(&entry, &e),
+                    },
+                    Err(e) => self.spill.reject(line.clone(), &e.to_string()),
+                }
+            }
+            if let Err(e) = db::write_batch(&self.db_pool, &entries).await {
+                warn!("Replay of spilled transaction logs stopped: {:?}", e);
+                break;
+            }
+            written += chunk.len();
+        }
+
+        if let Err(e) = self.spill.put_back(&lines[written..]) {
+            error!("Failed to update spilled transaction logs: {:?}", e);
+        }
+        if written == lines.len() {
+            info!("All spilled transaction log entries written");
+        }
+    }
+
+    fn reject(&self, entry: &LogEntry, reason: &Error) {
+        match serde_json::to_string(entry) {
+            Ok(line) => self.spill.reject(line, &reason.to_string()),
+            Err(_) => error!("Rejected transaction log entry ({}): {:?}", reason, entry),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(amount: u128) -> TransactionLog {
+        TransactionLog {
+            market: "0x01".to_string(),
+            wallet: "0x02".to_string(),
+            total_amount: amount,
+            matches_len: 1,
+            tx_id: "0x03".to_string(),
+            gas_used: 10,
+            match_time_ms: 1,
+            buy_orders: 1,
+            sell_orders: 1,
+            receive_time_ms: 2,
+            post_time_ms: 3,
+            submitted_at: Utc::now(),
+            completed_at: Utc::now(),
+            block_height: Some(7),
+            trades: vec![MatchedTrade {
+                buy_order_id: "0x04".to_string(),
+                sell_order_id: "0x05".to_string(),
+                buy_user: "0x06".to_string(),
+                sell_user: "0x07".to_string(),
+                price: u128::MAX,
+                amount,
+            }],
+        }
+    }
+
+    #[test]
+    fn confirmed_entry_round_trips_above_u64() {
+        let line = serde_json::to_string(&LogEntry::Confirmed(transaction(u128::MAX))).unwrap();
+        match serde_json::from_str::<LogEntry>(&line).unwrap() {
+            LogEntry::Confirmed(log) => {
+                assert_eq!(log.total_amount, u128::MAX);
+                assert_eq!(log.trades[0].price, u128::MAX);
+                assert_eq!(log.trades[0].amount, u128::MAX);
+            }
+            entry => panic!("unexpected entry {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn economics_entry_round_trips_above_u64() {
+        let entry = LogEntry::Economics(BatchEconomicsLog {
+            market: "0x01".to_string(),
+            decided_at: Utc::now(),
+            decision: "submitted".to_string(),
+            matches: 1,
+            orders: 2,
+            estimated_gas: 100,
+            gas_price: 1,
+            estimated_fee: u128::MAX,
+            estimated_fee_quote: u128::MAX,
+            expected_revenue: u64::MAX as u128 + 1,
+        });
+        let line = serde_json::to_string(&entry).unwrap();
+        match serde_json::from_str::<LogEntry>(&line).unwrap() {
+            LogEntry::Economics(log) => {
+                assert_eq!(log.estimated_fee, u128::MAX);
+                assert_eq!(log.estimated_fee_quote, u128::MAX);
+                assert_eq!(log.expected_revenue, u64::MAX as u128 + 1);
+            }
+            entry => panic!("unexpected entry {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn failed_entry_keeps_its_transaction_status() {
+        let entry = LogEntry::Failed(FailedTransactionLog {
+            market: "0x01".to_string(),
+            wallet: "0x02".to_string(),
+            order_ids: vec!["0x04".to_string()],
+            reason: FailureReason::Revert,
+            revert_code: Some("OrderNotFound".to_string()),
+            error: "Reverted".to_string(),
+            submitted_at: Utc::now(),
+            failed_at: Utc::now(),
+            transaction: Some(SentTransaction {
+                tx_id: "0x03".to_string(),
+                status: TransactionStatus::Reverted,
+                gas_used: Some(10),
+                block_height: Some(7),
+            }),
+        });
+        let mut value = serde_json::to_value(&entry).unwrap();
+        assert_eq!(value["transaction"]["status"], "reverted");
+
+        // Entries spilled before the status was recorded have no transaction.
+        value.as_object_mut().unwrap().remove("transaction");
+        match serde_json::from_value::<LogEntry>(value).unwrap() {
+            LogEntry::Failed(log) => assert!(log.transaction.is_none()),
+            entry => panic!("unexpected entry {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn reads_numeric_amounts_spilled_before() {
+        let mut value = serde_json::to_value(LogEntry::Confirmed(transaction(5))).unwrap();
+        value["total_amount"] = serde_json::json!(5);
+        value["trades"][0]["price"] = serde_json::json!(6);
+        value["trades"][0]["amount"] = serde_json::json!(5);
+        match serde_json::from_value::<LogEntry>(value).unwrap() {
+            LogEntry::Confirmed(log) => {
+                assert_eq!(log.total_amount, 5);
+                assert_eq!(log.trades[0].price, 6);
+            }
+            entry => panic!("unexpected entry {:?}", entry),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use log::error;
+
+use super::LogEntry;
+use crate::metrics;
+
+/// Local JSON-lines file holding log entries that could not be written to
+/// Postgres. Entries are moved to a separate replay file while they are being
+/// written back, so new spills never race with a replay.
+pub struct SpillFile {
+    path: PathBuf,
+    replay_path: PathBuf,
+    rejected_path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl SpillFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            replay_path: path.with_extension("replay"),
+            rejected_path: path.with_extension("rejected"),
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, entries: &[LogEntry]) {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = append_lines(&self.path, entries.iter().filter_map(to_line)) {
+            error!(
+                "Failed to spill {} transaction log entries to {:?}: {:?}, entries: {:?}",
+                entries.len(),
+                self.path,
+                e,
+                entries
+            );
+            return;
+        }
+        metrics::TRANSACTION_LOG_SPILLED.inc_by(entries.len() as u64);
+    }
+
+    /// Sets aside a line that can never be written, for manual inspection.
+    pub fn reject(&self, line: String, reason: &str) {
+        error!("Rejected transaction log entry ({}): {}", reason, line);
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = append_lines(&self.rejected_path, std::iter::once(line)) {
+            error!("Failed to write {:?}: {:?}", self.rejected_path, e);
+        }
+    }
+
+    pub fn has_pending(&self) -> bool {
+        self.path.exists() || self.replay_path.exists()
+    }
+
+    /// Lines to replay: a replay left over from an earlier attempt, otherwise
+    /// everything spilled so far.
+    pub fn take(&self) -> io::Result<Vec<String>> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        if !self.replay_path.exists() {
+            if !self.path.exists() {
+                return Ok(Vec::new());
+            }
+            fs::rename(&self.path, &self.replay_path)?;
+        }
+        Ok(fs::read_to_string(&self.replay_path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Keeps `remaining` for the next replay, or removes the replay file once
+    /// everything has been written.
+    pub fn put_back(&self, remaining: &[String]) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        if remaining.is_empty() {
+            return fs::remove_file(&self.replay_path);
+        }
+        fs::write(&self.replay_path, remaining.join("\n") + "\n")
+    }
+}
+
+fn to_line(entry: &LogEntry) -> Option<String> {
+    match serde_json::to_string(entry) {
+        Ok(line) => Some(line),
+        Err(e) => {
+            error!(
+                "Failed to serialize transaction log entry {:?}: {:?}",
+                entry, e
+            );
+            None
+        }
+    }
+}
+
+fn append_lines(path: &Path, lines: impl Iterator<Item = String>) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for line in lines {
+        writeln!(file, "{}", line)?;
+    }
+    file.sync_data()
+}
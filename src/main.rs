@@ -1,77 +1,267 @@
-use market::SparkMatcher;
+use events::EventBus;
+use fuels::accounts::provider::Provider;
+use health::HealthState;
+use leader::LeaderElection;
+use log::{error, info};
+use management::snapshot::SnapshotStore;
+use market::{MarketRegistry, SparkMatcher};
 use sqlx::PgPool;
-use tokio::signal;
-use tokio::sync::mpsc;
+use stats::StatsRollup;
+use std::sync::Arc;
+use supervisor::{RestartPolicy, Supervisor};
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::Duration;
 
 mod api;
 mod config;
+mod database;
 mod error;
+mod events;
+mod health;
+mod leader;
 mod logger;
 mod management;
 mod market;
+mod metrics;
 mod model;
+mod stats;
+mod supervisor;
 mod util;
+mod wallet;
 mod web;
 mod websocket;
 
 use crate::error::Error;
-use management::manager::OrderManager;
 use url::Url;
+use util::shutdown::wait_for_signal;
+use util::Shutdown;
+use wallet::WalletPool;
 use websocket::client::WebSocketClient;
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     dotenv::dotenv().ok();
 
+    let database_url = config::ev("DATABASE_URL")?;
+    let db_pool = PgPool::connect(&database_url).await?;
+    if std::env::args().any(|arg| arg == "--migrate-only") {
+        return database::migrate(&db_pool).await;
+    }
+    if config::ev_or("MIGRATE_ON_STARTUP", true) {
+        database::migrate(&db_pool).await?;
+    }
+    database::check_schema(&db_pool).await?;
+
     let ws_url = Url::parse(&config::ev("WEBSOCKET_URL")?)?;
 
-    let websocket_client = WebSocketClient::new(ws_url);
-    let order_manager = OrderManager::new();
-    let arc_order_manager = order_manager.clone();
+    let provider = Provider::connect("testnet.fuel.network").await?;
+    let wallet_pool = WalletPool::from_env(&provider)?;
 
-    let database_url = config::ev("DATABASE_URL")?;
-    let db_pool = PgPool::connect(&database_url).await.unwrap();
-
-    let spark_matcher = SparkMatcher::new(arc_order_manager.clone()).await?;
-
-    let (tx, mut rx) = mpsc::channel(100);
-
-    let ws_task = tokio::spawn(async move {
-        if let Err(e) = websocket_client.connect(tx).await {
-            eprintln!("WebSocket error: {}", e);
-        }
-    });
-
-    let manager_task = tokio::spawn(async move {
-        while let Some(order) = rx.recv().await {
-            order_manager.add_order(order).await;
-        }
-    });
-
-    let matcher_task = tokio::spawn(async move {
-        if let Err(e) = spark_matcher.run().await {
-            eprintln!("SparkMatcher error: {}", e);
-        }
-    });
-
-    let rocket_task = tokio::spawn(async {
-        let rocket = web::server::rocket(db_pool, arc_order_manager);
-        let _ = rocket.launch().await;
-    });
-
-    let ctrl_c_task = tokio::spawn(async {
-        signal::ctrl_c().await.expect("failed to listen for event");
-        println!("Ctrl+C received!");
-    });
-
-    let _ = tokio::select! {
-        _ = ws_task => { println!("WebSocket task finished"); },
-        _ = manager_task => { println!("Order manager task finished"); },
-        _ = matcher_task => { println!("SparkMatcher task finished"); },
-        _ = rocket_task => { println!("Rocket server task finished"); },
-        _ = ctrl_c_task => { println!("Shutting down..."); },
-    };
-
-    println!("Application is shutting down.");
+    let events = EventBus::new(config::ev_or("EVENT_BUFFER_SIZE", 1024));
+    let registry =
+        MarketRegistry::from_env(events.clone(), &wallet_pool.wallets()[0].account).await?;
+    let health = HealthState::new();
+    let websocket_client = WebSocketClient::new(ws_url, registry.clone(), health.clone());
+
+    let (log_queue, log_writer) = logger::channel(db_pool.clone());
+
+    let snapshots = SnapshotStore::from_env(db_pool.clone());
+    if let Some(snapshots) = &snapshots {
+        snapshots.restore(&registry).await?;
+    }
+
+    let shutdown = Shutdown::new();
+    let shutdown_timeout = Duration::from_secs(config::ev_or("SHUTDOWN_TIMEOUT_SECS", 30));
+    let supervisor = Supervisor::new(shutdown.clone());
+
+    let leader = LeaderElection::from_env()?;
+    let leader_release = Shutdown::new();
+    if let Some(leader) = leader.clone() {
+        let release = leader_release.clone();
+        supervisor
+            .spawn("leader-election", RestartPolicy::OnFailure, move || {
+                let leader = leader.clone();
+                let release = release.clone();
+                async move { leader.run(release).await }
+            })
+            .await;
+    }
+
+    // Snapshots are taken until matchers have drained, so the final one
+    // reflects the outcome of in-flight transactions.
+    let matchers_drained = Shutdown::new();
+    if let Some(snapshots) = snapshots {
+        let snapshot_registry = registry.clone();
+        let snapshot_leader = leader.clone();
+        let drained = matchers_drained.clone();
+        supervisor
+            .spawn("book-snapshot", RestartPolicy::Always, move || {
+                let snapshots = snapshots.clone();
+                let registry = snapshot_registry.clone();
+                let leader = snapshot_leader.clone();
+                let drained = drained.clone();
+                async move { snapshots.run(registry, leader, drained).await }
+            })
+            .await;
+    }
+
+    let log_writer = Arc::new(log_writer);
+    let log_spill = log_writer.clone();
+    supervisor
+        .spawn("transaction-log", RestartPolicy::OnFailure, move || {
+            let writer = log_writer.clone();
+            async move { writer.run().await }
+        })
+        .await;
+
+    let rollup = Arc::new(StatsRollup::from_env(db_pool.clone()));
+    supervisor
+        .spawn("stats-rollup", RestartPolicy::Always, move || {
+            let rollup = rollup.clone();
+            async move { rollup.run().await }
+        })
+        .await;
+
+    let order_ttl = Duration::from_secs(config::ev_or("ORDER_TTL_SECS", 300));
+    let sweep_interval = Duration::from_secs(config::ev_or("ORDER_SWEEP_INTERVAL_SECS", 10));
+    let sweeper_registry = registry.clone();
+    supervisor
+        .spawn("order-sweeper", RestartPolicy::Always, move || {
+            let registry = sweeper_registry.clone();
+            async move {
+                management::sweeper::sweep_stale_orders(registry, order_ttl, sweep_interval).await;
+                Ok(())
+            }
+        })
+        .await;
+
+    let monitor_pool = wallet_pool.clone();
+    let poll_interval = Duration::from_secs(config::ev_or("WALLET_BALANCE_POLL_SECS", 30));
+    supervisor
+        .spawn("wallet-monitor", RestartPolicy::Always, move || {
+            let pool = monitor_pool.clone();
+            async move {
+                wallet::monitor::monitor_balances(pool, poll_interval).await;
+                Ok(())
+            }
+        })
+        .await;
+
+    for market in registry.markets() {
+        let spark_matcher = Arc::new(
+            SparkMatcher::new(
+                market.clone(),
+                wallet_pool.clone(),
+                log_queue.clone(),
+                leader.clone(),
+                health.clone(),
+                events.clone(),
+            )
+            .await?,
+        );
+        let matcher_shutdown = shutdown.clone();
+        supervisor
+            .spawn(
+                &format!("matcher:{}", market.id),
+                RestartPolicy::OnFailure,
+                move || {
+                    let spark_matcher = spark_matcher.clone();
+                    let shutdown = matcher_shutdown.clone();
+                    async move { spark_matcher.run(shutdown).await }
+                },
+            )
+            .await;
+    }
+    drop(log_queue);
+
+    let (tx, rx) = mpsc::channel(100);
+
+    let websocket_client = Arc::new(websocket_client);
+    let ws_shutdown = shutdown.clone();
+    supervisor
+        .spawn("websocket", RestartPolicy::Always, move || {
+            let client = websocket_client.clone();
+            let tx = tx.clone();
+            let shutdown = ws_shutdown.clone();
+            async move {
+                client
+                    .connect(tx, shutdown)
+                    .await
+                    .map_err(|e| Error::TaskError(e.to_string()))
+            }
+        })
+        .await;
+
+    let rx = Arc::new(Mutex::new(rx));
+    let manager_registry = registry.clone();
+    supervisor
+        .spawn("order-manager", RestartPolicy::OnFailure, move || {
+            let rx = rx.clone();
+            let registry = manager_registry.clone();
+            async move {
+                let mut rx = rx.lock().await;
+                while let Some(order) = rx.recv().await {
+                    match registry.get(&order.market) {
+                        Some(market) => market.order_manager.add_order(order).await,
+                        None => {
+                            metrics::ORDERS_REJECTED
+                                .with_label_values(&[&order.market, "unknown_market"])
+                                .inc();
+                            error!("Order {} for unknown market {}", order.id, order.market)
+                        }
+                    }
+                }
+                Ok(())
+            }
+        })
+        .await;
+
+    let web_supervisor = supervisor.clone();
+    let web_shutdown = shutdown.clone();
+    supervisor
+        .spawn("web", RestartPolicy::OnFailure, move || {
+            let rocket = web::server::rocket(
+                db_pool.clone(),
+                registry.clone(),
+                wallet_pool.clone(),
+                web_supervisor.clone(),
+                leader.clone(),
+                health.clone(),
+                events.clone(),
+            );
+            let shutdown = web_shutdown.clone();
+            async move {
+                let rocket = rocket.ignite().await?;
+                let handle = rocket.shutdown();
+                tokio::spawn(async move {
+                    shutdown.wait().await;
+                    handle.notify();
+                });
+                rocket.launch().await?;
+                Ok(())
+            }
+        })
+        .await;
+
+    wait_for_signal().await;
+    info!("Shutting down...");
+
+    // Stop ingest and matching, let in-flight transactions finish, then
+    // flush the transaction log and stop the web server.
+    shutdown.trigger();
+    supervisor.join("websocket", shutdown_timeout).await;
+    supervisor.join("order-manager", shutdown_timeout).await;
+    supervisor
+        .join_prefix("matcher:", shutdown_timeout + Duration::from_secs(5))
+        .await;
+    matchers_drained.trigger();
+    supervisor.join("book-snapshot", shutdown_timeout).await;
+    leader_release.trigger();
+    supervisor.join("leader-election", shutdown_timeout).await;
+    supervisor.join("transaction-log", shutdown_timeout).await;
+    log_spill.spill_queued().await;
+    supervisor.join("web", shutdown_timeout).await;
+
+    info!("Application is shutting down.");
     Ok(())
 }
@@ -1,76 +1,322 @@
-use crate::model::{OrderType, SpotOrder};
+use crate::events::{EventBus, MarketEvent};
+use crate::management::snapshot::BookSnapshot;
+use crate::model::{BookDepth, OrderType, PriceLevel, SpotOrder, TopOfBook};
+use chrono::Utc;
 use log::info;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Notify, RwLock};
+use tokio::time::{Duration, Instant};
 
 pub struct OrderManager {
+    pub market: String,
     pub buy_orders: RwLock<BTreeMap<u128, Vec<SpotOrder>>>,
     pub sell_orders: RwLock<BTreeMap<u128, Vec<SpotOrder>>>,
+    /// Orders included in a transaction that has not completed yet.
+    pub pending_orders: RwLock<HashSet<String>>,
+    /// Orders from failed transactions, held back from matching until the deadline.
+    pub quarantined_orders: RwLock<HashMap<String, Instant>>,
+    /// Signalled whenever a change may have made the book crossable.
+    pub changed: Notify,
+    /// When each order was last reported by the indexer.
+    pub last_seen: RwLock<HashMap<String, Instant>>,
+    /// Orders restored from a snapshot that the indexer has not reported yet.
+    restored: RwLock<HashMap<String, (OrderType, u128)>>,
+    events: Arc<EventBus>,
 }
 
 impl OrderManager {
-    pub fn new() -> Arc<Self> {
+    pub fn new(market: String, events: Arc<EventBus>) -> Arc<Self> {
         Arc::new(Self {
+            market,
             buy_orders: RwLock::new(BTreeMap::new()),
             sell_orders: RwLock::new(BTreeMap::new()),
+            pending_orders: RwLock::new(HashSet::new()),
+            quarantined_orders: RwLock::new(HashMap::new()),
+            changed: Notify::new(),
+            last_seen: RwLock::new(HashMap::new()),
+            restored: RwLock::new(HashMap::new()),
+            events,
         })
     }
 
     pub async fn add_order(&self, order: SpotOrder) {
-        let mut order_map = match order.order_type {
-            OrderType::Buy => self.buy_orders.write().await,
-            OrderType::Sell => self.sell_orders.write().await,
-        };
+        self.insert_order(order, Instant::now()).await;
+    }
 
-        let orders = order_map.entry(order.price).or_default();
+    /// Adds or replaces an order, recording it as last reported at `seen_at`.
+    async fn insert_order(&self, order: SpotOrder, seen_at: Instant) {
+        let (price, order_type) = (order.price, order.order_type);
+        self.last_seen
+            .write()
+            .await
+            .insert(order.id.clone(), seen_at);
+        {
+            let mut order_map = match order_type {
+                OrderType::Buy => self.buy_orders.write().await,
+                OrderType::Sell => self.sell_orders.write().await,
+            };
+
+            let orders = order_map.entry(order.price).or_default();
+            let before = level_summary(orders);
+
+            if let Some(existing_order) = orders.iter_mut().find(|o| o.id == order.id) {
+                *existing_order = order;
+            } else {
+                orders.push(order);
+            }
+            // The indexer re-sends every active order on each frame, so most
+            // calls leave the level as it was.
+            if level_summary(orders) != before {
+                self.publish_level(order_type, price, orders);
+            }
+        }
 
-        if let Some(existing_order) = orders.iter_mut().find(|o| o.id == order.id) {
-            *existing_order = order;
-        } else {
-            orders.push(order);
+        if self.crosses(price, order_type).await {
+            self.changed.notify_one();
         }
     }
 
-    pub async fn clear_orders(&self) {
-        let mut buy_orders = self.buy_orders.write().await;
-        let mut sell_orders = self.sell_orders.write().await;
-        buy_orders.clear();
-        sell_orders.clear();
-        info!("All orders have been cleared from OrderManager");
+    /// Whether an order at `price` would cross the best price on the opposite side.
+    async fn crosses(&self, price: u128, order_type: OrderType) -> bool {
+        match order_type {
+            OrderType::Buy => {
+                let sell_orders = self.sell_orders.read().await;
+                sell_orders
+                    .keys()
+                    .next()
+                    .is_some_and(|best_ask| price >= *best_ask)
+            }
+            OrderType::Sell => {
+                let buy_orders = self.buy_orders.read().await;
+                buy_orders
+                    .keys()
+                    .next_back()
+                    .is_some_and(|best_bid| price <= *best_bid)
+            }
+        }
+    }
+
+    /// Removes the given orders from both sides of the book, e.g. once a
+    /// transaction that filled them has gone through. Partially filled orders
+    /// come back with their new amount on the next indexer frame.
+    pub async fn remove_orders(&self, order_ids: &[String]) {
+        let ids: HashSet<&String> = order_ids.iter().collect();
+        let mut found = Vec::new();
+        for (side, book) in [
+            (OrderType::Buy, &self.buy_orders),
+            (OrderType::Sell, &self.sell_orders),
+        ] {
+            for order in book.read().await.values().flatten() {
+                if ids.contains(&order.id) {
+                    found.push((order.id.clone(), order.price, side));
+                }
+            }
+        }
+        for (id, price, side) in &found {
+            self.remove_order(id, *price, *side).await;
+        }
     }
 
     pub async fn remove_order(&self, order_id: &str, price: u128, order_type: OrderType) {
+        self.last_seen.write().await.remove(order_id);
         let mut order_map = match order_type {
             OrderType::Buy => self.buy_orders.write().await,
             OrderType::Sell => self.sell_orders.write().await,
         };
 
         if let Some(orders) = order_map.get_mut(&price) {
+            let before = orders.len();
             orders.retain(|order| order.id != order_id);
+            if orders.len() != before {
+                self.publish_level(order_type, price, orders);
+            }
             if orders.is_empty() {
                 order_map.remove(&price);
             }
         }
     }
 
-    pub async fn get_orders(&self, price: u128, order_type: OrderType) -> Vec<SpotOrder> {
-        let order_map = match order_type {
-            OrderType::Buy => self.buy_orders.read().await,
-            OrderType::Sell => self.sell_orders.read().await,
-        };
+    fn publish_level(&self, side: OrderType, price: u128, orders: &[SpotOrder]) {
+        let (total_amount, order_count) = level_summary(orders);
+        self.events.publish(MarketEvent::BookLevel {
+            market: self.market.clone(),
+            side,
+            price,
+            total_amount,
+            order_count,
+        });
+    }
+
+    pub async fn mark_pending(&self, order_ids: &[String]) {
+        let mut pending = self.pending_orders.write().await;
+        pending.extend(order_ids.iter().cloned());
+    }
+
+    pub async fn release_pending(&self, order_ids: &[String]) {
+        let mut pending = self.pending_orders.write().await;
+        for id in order_ids {
+            pending.remove(id);
+        }
+        drop(pending);
+        self.changed.notify_one();
+    }
+
+    pub async fn quarantine(&self, order_ids: &[String], duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut quarantined = self.quarantined_orders.write().await;
+        for id in order_ids {
+            quarantined.insert(id.clone(), until);
+        }
+    }
+
+    /// Ids that must not be matched right now: pending ones and those still
+    /// in quarantine. Expired quarantine entries are dropped along the way.
+    pub async fn excluded_order_ids(&self) -> HashSet<String> {
+        let mut excluded = self.pending_orders.read().await.clone();
+        let mut quarantined = self.quarantined_orders.write().await;
+        let now = Instant::now();
+        quarantined.retain(|_, until| *until > now);
+        excluded.extend(quarantined.keys().cloned());
+        excluded
+    }
+
+    /// Removes orders the indexer has not reported for longer than `ttl`.
+    /// Pending orders are kept until their transaction completes.
+    pub async fn evict_stale(&self, ttl: Duration) -> usize {
+        let pending = self.pending_orders.read().await.clone();
+        let stale: HashSet<String> = self
+            .last_seen
+            .read()
+            .await
+            .iter()
+            .filter(|(id, seen)| seen.elapsed() > ttl && !pending.contains(*id))
+            .map(|(id, _)| id.clone())
+            .collect();
+        if stale.is_empty() {
+            return 0;
+        }
+
+        let mut evicted = Vec::new();
+        for (side, book) in [
+            (OrderType::Buy, &self.buy_orders),
+            (OrderType::Sell, &self.sell_orders),
+        ] {
+            for order in book.read().await.values().flatten() {
+                if stale.contains(&order.id) {
+                    evicted.push((order.id.clone(), order.price, side));
+                }
+            }
+        }
+        for (id, price, side) in &evicted {
+            self.remove_order(id, *price, *side).await;
+        }
+        // Drop timestamps of orders that are no longer in the book.
+        let mut last_seen = self.last_seen.write().await;
+        for id in &stale {
+            last_seen.remove(id);
+        }
+        evicted.len()
+    }
 
-        order_map.get(&price).cloned().unwrap_or_else(Vec::new)
+    pub async fn snapshot(&self) -> BookSnapshot {
+        let (mut orders, sell_orders) = self.get_all_orders2().await;
+        orders.extend(sell_orders);
+        let pending = self.pending_orders.read().await.iter().cloned().collect();
+        let now = Instant::now();
+        let quarantined_until = self
+            .quarantined_orders
+            .read()
+            .await
+            .iter()
+            .filter(|(_, until)| **until > now)
+            .map(|(id, until)| {
+                let remaining = chrono::Duration::from_std(*until - now).unwrap_or_default();
+                (id.clone(), Utc::now() + remaining)
+            })
+            .collect();
+        BookSnapshot {
+            market: self.market.clone(),
+            taken_at: Utc::now(),
+            orders,
+            pending,
+            quarantined_until,
+        }
+    }
+
+    /// Loads a snapshot into the book. Orders that were pending are
+    /// quarantined for `pending_quarantine`, since their transaction may have
+    /// gone through. Restored orders stay until `reconcile` finds them missing
+    /// from the indexer feed, and count as last seen when the snapshot was
+    /// taken, so freshness and the TTL apply to them as to any other order.
+    pub async fn restore(&self, snapshot: BookSnapshot, pending_quarantine: Duration) {
+        {
+            let mut restored = self.restored.write().await;
+            for order in &snapshot.orders {
+                restored.insert(order.id.clone(), (order.order_type, order.price));
+            }
+        }
+        let age = (Utc::now() - snapshot.taken_at)
+            .to_std()
+            .unwrap_or_default();
+        let seen_at = Instant::now().checked_sub(age).unwrap_or_else(Instant::now);
+        for order in snapshot.orders {
+            self.insert_order(order, seen_at).await;
+        }
+
+        let now = Instant::now();
+        let mut quarantined = self.quarantined_orders.write().await;
+        for (id, until) in snapshot.quarantined_until {
+            if let Ok(remaining) = (until - Utc::now()).to_std() {
+                quarantined.insert(id, now + remaining);
+            }
+        }
+        for id in snapshot.pending {
+            quarantined.insert(id, now + pending_quarantine);
+        }
+    }
+
+    /// Called with the ids of one side of the first indexer frame: restored
+    /// orders on that side that the indexer did not report are removed.
+    pub async fn reconcile(&self, side: OrderType, seen: &HashSet<String>) {
+        if self.restored.read().await.is_empty() {
+            return;
+        }
+        let stale: Vec<(String, u128)> = {
+            let mut restored = self.restored.write().await;
+            let mut stale = Vec::new();
+            restored.retain(|id, (order_type, price)| {
+                if *order_type != side {
+                    return true;
+                }
+                if !seen.contains(id) {
+                    stale.push((id.clone(), *price));
+                }
+                false
+            });
+            stale
+        };
+        if !stale.is_empty() {
+            info!(
+                "Removing {} restored {:?} orders of {} not reported by the indexer",
+                stale.len(),
+                side,
+                self.market
+            );
+        }
+        for (id, price) in stale {
+            self.remove_order(&id, price, side).await;
+        }
     }
 
     pub async fn get_all_buy_orders(&self) -> Vec<SpotOrder> {
         let buy_orders = self.buy_orders.read().await;
-        buy_orders.values().cloned().flatten().collect()
+        buy_orders.values().flatten().cloned().collect()
     }
 
     pub async fn get_all_sell_orders(&self) -> Vec<SpotOrder> {
         let sell_orders = self.sell_orders.read().await;
-        sell_orders.values().cloned().flatten().collect()
+        sell_orders.values().flatten().cloned().collect()
     }
 
     pub async fn get_all_orders2(&self) -> (Vec<SpotOrder>, Vec<SpotOrder>) {
@@ -79,18 +325,59 @@ impl OrderManager {
         (buy_orders, sell_orders)
     }
 
-    pub async fn log_orders(&self) {
+    /// Aggregated price levels, up to `levels` per side, best prices first.
+    pub async fn depth(&self, levels: usize) -> BookDepth {
         let buy_orders = self.buy_orders.read().await;
         let sell_orders = self.sell_orders.read().await;
 
-        info!("Current Buy Orders:");
-        for (price, orders) in buy_orders.iter() {
-            info!("Price: {} -> Orders: {:?}", price, orders);
+        BookDepth {
+            bids: aggregate_levels(buy_orders.iter().rev(), levels),
+            asks: aggregate_levels(sell_orders.iter(), levels),
         }
+    }
 
-        info!("Current Sell Orders:");
-        for (price, orders) in sell_orders.iter() {
-            info!("Price: {} -> Orders: {:?}", price, orders);
-        }
+    pub async fn top_of_book(&self) -> TopOfBook {
+        let best_bid = self
+            .buy_orders
+            .read()
+            .await
+            .iter()
+            .rev()
+            .find(|(_, orders)| !orders.is_empty())
+            .map(|(price, _)| *price);
+        let best_ask = self
+            .sell_orders
+            .read()
+            .await
+            .iter()
+            .find(|(_, orders)| !orders.is_empty())
+            .map(|(price, _)| *price);
+        TopOfBook::new(best_bid, best_ask)
     }
 }
+
+fn aggregate_levels<'a>(
+    levels: impl Iterator<Item = (&'a u128, &'a Vec<SpotOrder>)>,
+    limit: usize,
+) -> Vec<PriceLevel> {
+    levels
+        .filter(|(_, orders)| !orders.is_empty())
+        .take(limit)
+        .map(|(price, orders)| {
+            let (total_amount, order_count) = level_summary(orders);
+            PriceLevel {
+                price: *price,
+                total_amount,
+                order_count,
+            }
+        })
+        .collect()
+}
+
+/// Total amount and number of orders at one price level.
+fn level_summary(orders: &[SpotOrder]) -> (u128, usize) {
+    let total = orders
+        .iter()
+        .fold(0u128, |sum, o| sum.saturating_add(o.amount));
+    (total, orders.len())
+}
@@ -1 +1,3 @@
 pub mod manager;
+pub mod snapshot;
+pub mod sweeper;
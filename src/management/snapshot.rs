@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use tokio::time::{self, Duration};
+
+use crate::config::ev_or;
+use crate::error::Error;
+use crate::leader::LeaderElection;
+use crate::market::MarketRegistry;
+use crate::model::SpotOrder;
+use crate::util::Shutdown;
+
+/// Order book of one market together with the orders held back from matching.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookSnapshot {
+    pub market: String,
+    pub taken_at: DateTime<Utc>,
+    pub orders: Vec<SpotOrder>,
+    /// Orders in transactions that had not completed when the snapshot was taken.
+    pub pending: Vec<String>,
+    pub quarantined_until: HashMap<String, DateTime<Utc>>,
+}
+
+/// Periodically stores book snapshots in Postgres and restores them on
+/// startup, so matching can resume before the indexer feed catches up.
+pub struct SnapshotStore {
+    db_pool: PgPool,
+    interval: Duration,
+    max_age: Duration,
+    /// Pending orders are quarantined this long after a restore, since the
+    /// outcome of their transaction is unknown.
+    pending_quarantine: Duration,
+}
+
+impl SnapshotStore {
+    /// Returns `None` when `BOOK_SNAPSHOT` is disabled.
+    pub fn from_env(db_pool: PgPool) -> Option<Arc<Self>> {
+        if !ev_or("BOOK_SNAPSHOT", true) {
+            return None;
+        }
+        // Restored orders keep their snapshot time as last seen, so an older
+        // snapshot would only bring back orders the matcher skips as stale.
+        let freshness = ev_or("MATCHER_FRESHNESS_SECS", 60);
+        Some(Arc::new(Self {
+            db_pool,
+            interval: Duration::from_secs(ev_or("BOOK_SNAPSHOT_INTERVAL_SECS", 10)),
+            max_age: Duration::from_secs(
+                ev_or("BOOK_SNAPSHOT_MAX_AGE_SECS", freshness).min(freshness),
+            ),
+            pending_quarantine: Duration::from_secs(ev_or("QUARANTINE_SECS", 30)),
+        }))
+    }
+
+    /// Loads the latest snapshot of every market that is recent enough.
+    pub async fn restore(&self, registry: &MarketRegistry) -> Result<(), Error> {
+        for market in registry.markets() {
+            let row = sqlx::query!(
+                r#"SELECT snapshot AS "snapshot: Json<BookSnapshot>" FROM book_snapshots WHERE market = $1"#,
+                market.id
+            )
+            .fetch_optional(&self.db_pool)
+            .await?;
+            let Some(Json(snapshot)) = row.map(|row| row.snapshot) else {
+                continue;
+            };
+
+            let age = (Utc::now() - snapshot.taken_at)
+                .to_std()
+                .unwrap_or_default();
+            if age > self.max_age {
+                info!(
+                    "Ignoring book snapshot of {} taken {:?} ago",
+                    market.id, age
+                );
+                continue;
+            }
+            info!(
+                "Restoring {} orders for {} from a snapshot taken {:?} ago",
+                snapshot.orders.len(),
+                market.id,
+                age
+            );
+            market
+                .order_manager
+                .restore(snapshot, self.pending_quarantine)
+                .await;
+        }
+        Ok(())
+    }
+
+    pub async fn save(&self, snapshot: &BookSnapshot) -> Result<(), Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO book_snapshots (market, taken_at, snapshot)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (market) DO UPDATE SET taken_at = EXCLUDED.taken_at, snapshot = EXCLUDED.snapshot
+            "#,
+            snapshot.market,
+            snapshot.taken_at,
+            Json(snapshot) as _
+        )
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Saves snapshots every `interval` and once more on shutdown. With
+    /// leader election only the leader writes, since it owns the pending state.
+    pub async fn run(
+        &self,
+        registry: Arc<MarketRegistry>,
+        leader: Option<Arc<LeaderElection>>,
+        shutdown: Shutdown,
+    ) -> Result<(), Error> {
+        let mut ticker = time::interval(self.interval);
+        loop {
+            let stopping = tokio::select! {
+                _ = ticker.tick() => false,
+                _ = shutdown.wait() => true,
+            };
+            if leader.as_ref().is_none_or(|l| l.is_leader()) {
+                for market in registry.markets() {
+                    let snapshot = market.order_manager.snapshot().await;
+                    if let Err(e) = self.save(&snapshot).await {
+                        error!("Failed to save book snapshot of {}: {:?}", market.id, e);
+                    }
+                }
+            }
+            if stopping {
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use log::info;
+use tokio::time::{self, Duration};
+
+use crate::market::MarketRegistry;
+use crate::metrics;
+
+/// Evicts orders the indexer has stopped reporting for longer than `ttl`,
+/// e.g. ones cancelled or filled while the feed was lagging.
+pub async fn sweep_stale_orders(registry: Arc<MarketRegistry>, ttl: Duration, interval: Duration) {
+    let mut ticker = time::interval(interval);
+    loop {
+        ticker.tick().await;
+        for market in registry.markets() {
+            let evicted = market.order_manager.evict_stale(ttl).await;
+            if evicted > 0 {
+                info!(
+                    "Evicted {} orders of {} not seen for {:?}",
+                    evicted, market.id, ttl
+                );
+                metrics::ORDERS_EVICTED
+                    .with_label_values(&[&market.id])
+                    .inc_by(evicted as u64);
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use std::str::FromStr;
+
+use fuels::programs::calls::Execution;
+use fuels::types::AssetId;
+use log::info;
+use schemars::JsonSchema;
+use serde::Serialize;
+use sqlx::types::BigDecimal;
+
+use crate::config::market_ev;
+use crate::error::Error;
+use crate::market::contract::MarketContract;
+use crate::util::decimal::numeric;
+
+/// Assets a market trades and the precision of its raw integer amounts and
+/// prices. Matching works on the raw integers; these are only used to show
+/// them as decimals.
+#[derive(Debug, Clone)]
+pub struct MarketAssets {
+    pub base_asset: AssetId,
+    pub quote_asset: AssetId,
+    pub base_decimals: u32,
+    pub quote_decimals: u32,
+    pub price_decimals: u32,
+}
+
+impl MarketAssets {
+    /// Reads the market config from the contract. `BASE_ASSET`, `QUOTE_ASSET`,
+    /// `BASE_DECIMALS`, `QUOTE_DECIMALS` and `PRICE_DECIMALS` (per market)
+    /// override it; the contract is not read when all of them are set.
+    pub async fn load(index: usize, contract: &MarketContract) -> Result<Self, Error> {
+        let configured = (
+            setting(index, "BASE_ASSET", AssetId::from_str)?,
+            setting(index, "QUOTE_ASSET", AssetId::from_str)?,
+            setting(index, "BASE_DECIMALS", u32::from_str)?,
+            setting(index, "QUOTE_DECIMALS", u32::from_str)?,
+            setting(index, "PRICE_DECIMALS", u32::from_str)?,
+        );
+        if let (
+            Some(base_asset),
+            Some(quote_asset),
+            Some(base_decimals),
+            Some(quote_decimals),
+            Some(price_decimals),
+        ) = configured
+        {
+            return Ok(Self {
+                base_asset,
+                quote_asset,
+                base_decimals,
+                quote_decimals,
+                price_decimals,
+            });
+        }
+
+        let (base_asset, base_decimals, quote_asset, quote_decimals, _, price_decimals, ..) =
+            contract
+                .methods()
+                .config()
+                .simulate(Execution::StateReadOnly)
+                .await
+                .map_err(|e| {
+                    Error::MarketMetadataError(format!(
+                        "failed to read the config of market #{}: {:?}",
+                        index, e
+                    ))
+                })?
+                .value;
+        let assets = Self {
+            base_asset: configured.0.unwrap_or(base_asset),
+            quote_asset: configured.1.unwrap_or(quote_asset),
+            base_decimals: configured.2.unwrap_or(base_decimals),
+            quote_decimals: configured.3.unwrap_or(quote_decimals),
+            price_decimals: configured.4.unwrap_or(price_decimals),
+        };
+        info!("Market #{} assets: {:?}", index, assets);
+        Ok(assets)
+    }
+
+    /// Raw base amount in whole base asset units.
+    pub fn amount(&self, raw: u128) -> BigDecimal {
+        scaled(numeric(raw), self.base_decimals)
+    }
+
+    /// Raw price in quote asset units per base asset unit.
+    pub fn price(&self, raw: u128) -> BigDecimal {
+        scaled(numeric(raw), self.price_decimals)
+    }
+
+    /// Like [`amount`](Self::amount), for values read back from `NUMERIC` columns.
+    pub fn amount_numeric(&self, raw: &BigDecimal) -> BigDecimal {
+        scaled(raw.clone(), self.base_decimals)
+    }
+
+    /// Like [`price`](Self::price), for values read back from `NUMERIC` columns.
+    pub fn price_numeric(&self, raw: &BigDecimal) -> BigDecimal {
+        scaled(raw.clone(), self.price_decimals)
+    }
+
+    pub fn info(&self) -> AssetsInfo {
+        AssetsInfo {
+            base_asset: format!("0x{}", self.base_asset),
+            quote_asset: format!("0x{}", self.quote_asset),
+            base_decimals: self.base_decimals,
+            quote_decimals: self.quote_decimals,
+            price_decimals: self.price_decimals,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct AssetsInfo {
+    pub base_asset: String,
+    pub quote_asset: String,
+    pub base_decimals: u32,
+    pub quote_decimals: u32,
+    pub price_decimals: u32,
+}
+
+/// Unset settings are `None`; settings that do not parse are an error rather
+/// than silently replaced by the contract's value.
+fn setting<T, E: std::fmt::Debug>(
+    index: usize,
+    key: &str,
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> Result<Option<T>, Error> {
+    match market_ev(index, key) {
+        Ok(value) => parse(value.trim()).map(Some).map_err(|e| {
+            Error::MarketMetadataError(format!("invalid {} for market #{}: {:?}", key, index, e))
+        }),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Divides by `10^decimals` exactly, by shifting the decimal point.
+fn scaled(raw: BigDecimal, decimals: u32) -> BigDecimal {
+    let (digits, scale) = raw.into_bigint_and_exponent();
+    BigDecimal::new(digits, scale + decimals as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assets() -> MarketAssets {
+        MarketAssets {
+            base_asset: AssetId::zeroed(),
+            quote_asset: AssetId::zeroed(),
+            base_decimals: 8,
+            quote_decimals: 6,
+            price_decimals: 9,
+        }
+    }
+
+    #[test]
+    fn scaled_shifts_the_decimal_point() {
+        assert_eq!(scaled(numeric(123_456), 3).to_string(), "123.456");
+        assert_eq!(scaled(numeric(5), 4).to_string(), "0.0005");
+        assert_eq!(scaled(numeric(42), 0).to_string(), "42");
+    }
+
+    #[test]
+    fn amount_and_price_use_their_own_decimals() {
+        let assets = assets();
+        assert_eq!(
+            assets.amount(150_000_000),
+            BigDecimal::from_str("1.5").unwrap()
+        );
+        assert_eq!(
+            assets.price(65_000_250_000_000),
+            BigDecimal::from_str("65000.25").unwrap()
+        );
+    }
+
+    #[test]
+    fn numeric_views_match_the_raw_ones() {
+        let assets = assets();
+        let raw = u128::MAX;
+        assert_eq!(assets.amount_numeric(&numeric(raw)), assets.amount(raw));
+        assert_eq!(assets.price_numeric(&numeric(raw)), assets.price(raw));
+        assert_eq!(
+            assets.amount(raw).to_string(),
+            "3402823669209384634633746074317.68211455"
+        );
+    }
+}
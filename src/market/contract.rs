@@ -0,0 +1,35 @@
+use fuels::programs::calls::Execution;
+use fuels::types::ContractId;
+use spark_market_sdk::{SparkMarket, SparkMarketContract};
+
+use crate::error::Error;
+use crate::wallet::SignerAccount;
+
+/// Market contract handle whose transactions are funded and signed through a
+/// `SignerAccount`. Reads are simulated and never signed.
+pub type MarketContract = SparkMarket<SignerAccount>;
+
+/// Opens the market contract and checks that its major version matches the
+/// SDK the matcher was built with.
+pub async fn connect(
+    contract_id: ContractId,
+    account: SignerAccount,
+) -> Result<MarketContract, Error> {
+    let contract = MarketContract::new(contract_id, account);
+    let version = contract
+        .methods()
+        .config()
+        .simulate(Execution::StateReadOnly)
+        .await?
+        .value
+        .6;
+    if version & 0xFF0000 != SparkMarketContract::sdk_version() & 0xFF0000 {
+        return Err(Error::MarketMetadataError(format!(
+            "market {} has contract version {:#08x}, incompatible with SDK {}",
+            contract_id,
+            version,
+            SparkMarketContract::sdk_str_version()
+        )));
+    }
+    Ok(contract)
+}
@@ -0,0 +1,92 @@
+use crate::config::market_ev_or;
+use crate::model::SpotOrder;
+
+/// Size limits that keep gas from being spent on dust. Notional is
+/// `amount * price` in raw contract units. All limits default to 0 (off).
+#[derive(Debug, Clone, Default)]
+pub struct DustRules {
+    /// Orders smaller than this are kept in the book but not matched, and
+    /// partial-fill remainders below it are not matched further.
+    pub min_order_amount: u128,
+    pub min_order_notional: u128,
+    /// Smallest amount a single fill may have.
+    pub min_fill_amount: u128,
+    /// A batch must reach both of these to be submitted.
+    pub min_batch_amount: u128,
+    pub min_batch_notional: u128,
+}
+
+impl DustRules {
+    pub fn from_env(index: usize) -> Self {
+        Self {
+            min_order_amount: market_ev_or(index, "MIN_ORDER_AMOUNT", 0),
+            min_order_notional: market_ev_or(index, "MIN_ORDER_NOTIONAL", 0),
+            min_fill_amount: market_ev_or(index, "MIN_FILL_AMOUNT", 0),
+            min_batch_amount: market_ev_or(index, "MIN_BATCH_AMOUNT", 0),
+            min_batch_notional: market_ev_or(index, "MIN_BATCH_NOTIONAL", 0),
+        }
+    }
+
+    /// Which order minimum `amount` at `price` falls short of, if any.
+    pub fn below_order_minimum(&self, amount: u128, price: u128) -> Option<&'static str> {
+        if amount < self.min_order_amount {
+            Some("amount")
+        } else if notional(amount, price) < self.min_order_notional {
+            Some("notional")
+        } else {
+            None
+        }
+    }
+
+    pub fn is_dust(&self, order: &SpotOrder) -> bool {
+        self.below_order_minimum(order.amount, order.price)
+            .is_some()
+    }
+}
+
+pub fn notional(amount: u128, price: u128) -> u128 {
+    amount.saturating_mul(price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(min_order_amount: u128, min_order_notional: u128) -> DustRules {
+        DustRules {
+            min_order_amount,
+            min_order_notional,
+            ..DustRules::default()
+        }
+    }
+
+    #[test]
+    fn defaults_accept_everything() {
+        assert_eq!(DustRules::default().below_order_minimum(0, 0), None);
+    }
+
+    #[test]
+    fn amount_threshold_is_inclusive() {
+        let rules = rules(10, 0);
+        assert_eq!(rules.below_order_minimum(9, 1), Some("amount"));
+        assert_eq!(rules.below_order_minimum(10, 1), None);
+    }
+
+    #[test]
+    fn notional_threshold_is_inclusive() {
+        let rules = rules(0, 1_000);
+        assert_eq!(rules.below_order_minimum(9, 111), Some("notional"));
+        assert_eq!(rules.below_order_minimum(10, 100), None);
+    }
+
+    #[test]
+    fn amount_is_reported_before_notional() {
+        assert_eq!(rules(10, 1_000).below_order_minimum(1, 1), Some("amount"));
+    }
+
+    #[test]
+    fn notional_saturates() {
+        assert_eq!(notional(u128::MAX, 2), u128::MAX);
+        assert_eq!(rules(0, u128::MAX).below_order_minimum(u128::MAX, 2), None);
+    }
+}
@@ -0,0 +1,169 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Why a `match_order_many` submission failed, derived from the error text
+/// returned by the SDK.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    /// The contract reverted; the revert reason is stored separately.
+    Revert,
+    OutOfGas,
+    InsufficientFunds,
+    /// Node unreachable, request timed out or the connection dropped.
+    Network,
+    /// Inputs were spent by another transaction or the transaction was squeezed out.
+    UtxoConflict,
+    Other,
+}
+
+impl FailureReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureReason::Revert => "revert",
+            FailureReason::OutOfGas => "out_of_gas",
+            FailureReason::InsufficientFunds => "insufficient_funds",
+            FailureReason::Network => "network",
+            FailureReason::UtxoConflict => "utxo_conflict",
+            FailureReason::Other => "other",
+        }
+    }
+}
+
+/// Classified failure together with the revert reason, when there is one.
+#[derive(Debug, Clone)]
+pub struct FailureClass {
+    pub reason: FailureReason,
+    pub revert_code: Option<String>,
+}
+
+/// Classifies an SDK error message. A revert is checked first, so a contract
+/// error such as `InsufficientBalance` keeps its revert code; an out-of-gas
+/// panic is reported as a revert too and is told apart by its reason.
+pub fn classify(error: &str) -> FailureClass {
+    let lower = error.to_ascii_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|n| lower.contains(n));
+
+    if lower.contains(REVERTED) {
+        let revert_code = revert_code(error);
+        if revert_code
+            .as_deref()
+            .is_some_and(|code| code.eq_ignore_ascii_case("OutOfGas"))
+        {
+            return FailureClass {
+                reason: FailureReason::OutOfGas,
+                revert_code: None,
+            };
+        }
+        return FailureClass {
+            reason: FailureReason::Revert,
+            revert_code,
+        };
+    }
+
+    let reason = if has(&["outofgas", "out of gas"]) {
+        FailureReason::OutOfGas
+    } else if has(&["insufficientcoins", "not enough coins"]) {
+        FailureReason::InsufficientFunds
+    } else if has(&["squeezedout", "already spent", "utxo"]) {
+        FailureReason::UtxoConflict
+    } else if has(&[
+        "io: ",
+        "timed out",
+        "timeout",
+        "connection refused",
+        "connection reset",
+        "connection closed",
+        "error sending request",
+    ]) {
+        FailureReason::Network
+    } else {
+        FailureReason::Other
+    };
+    FailureClass {
+        reason,
+        revert_code: None,
+    }
+}
+
+const REVERTED: &str = "reverted:";
+
+/// Extracts the first token after `reverted:` (as printed by fuels), e.g. the
+/// contract's error enum variant or the numeric revert id.
+fn revert_code(error: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets valid for slicing `error`.
+    let start = error.to_ascii_lowercase().find(REVERTED)? + REVERTED.len();
+    error[start..]
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ')')
+        .find(|token| !token.is_empty())
+        .map(|token| token.trim_matches('"').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contract_revert_keeps_its_code() {
+        let class = classify("transaction reverted: InsufficientBalance, receipts: []");
+        assert_eq!(class.reason, FailureReason::Revert);
+        assert_eq!(class.revert_code.as_deref(), Some("InsufficientBalance"));
+    }
+
+    #[test]
+    fn out_of_gas_revert_is_out_of_gas() {
+        let class = classify("transaction reverted: OutOfGas, receipts: []");
+        assert_eq!(class.reason, FailureReason::OutOfGas);
+        assert_eq!(class.revert_code, None);
+    }
+
+    #[test]
+    fn coin_errors_are_insufficient_funds() {
+        for error in [
+            "provider: not enough coins to fit the target",
+            "InsufficientCoins { asset_id: 0x00 }",
+        ] {
+            assert_eq!(classify(error).reason, FailureReason::InsufficientFunds);
+        }
+    }
+
+    #[test]
+    fn squeezed_out_is_utxo_conflict() {
+        let class = classify("transaction squeezedOut: Transaction is not inserted");
+        assert_eq!(class.reason, FailureReason::UtxoConflict);
+    }
+
+    #[test]
+    fn network_errors() {
+        for error in [
+            "io: connection refused",
+            "provider: error sending request: operation timed out",
+        ] {
+            assert_eq!(classify(error).reason, FailureReason::Network);
+        }
+    }
+
+    #[test]
+    fn broad_words_are_not_classified() {
+        for error in [
+            "provider: invalid nonce",
+            "provider: unknown connection id format",
+            "validation: insufficient max fee",
+        ] {
+            assert_eq!(classify(error).reason, FailureReason::Other, "{}", error);
+        }
+    }
+
+    #[test]
+    fn revert_code_after_non_ascii_text() {
+        // `İ` grows by a byte when lowercased with `to_lowercase`.
+        let error = "İİİİ transaction reverted: \"MatchError\", receipts: []";
+        assert_eq!(revert_code(error).as_deref(), Some("MatchError"));
+    }
+
+    #[test]
+    fn revert_without_code() {
+        assert_eq!(revert_code("transaction reverted:"), None);
+        assert_eq!(revert_code("no revert here"), None);
+    }
+}
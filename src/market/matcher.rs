@@ -1,60 +1,274 @@
-use crate::config::ev;
+use crate::config::ev_or;
 use crate::error::Error;
-use crate::logger::{log_transactions, TransactionLog};
+use crate::events::{EventBus, MarketEvent};
+use crate::health::HealthState;
+use crate::leader::LeaderElection;
+use crate::logger::{
+    BatchEconomicsLog, FailedTransactionLog, LogEntry, LogQueue, SentTransaction, TransactionLog,
+    TransactionStatus,
+};
 use crate::management::manager::OrderManager;
-use crate::model::SpotOrder;
+use crate::market::assets::MarketAssets;
+use crate::market::contract::MarketContract;
+use crate::market::dust::{notional, DustRules};
+use crate::market::failure;
+use crate::market::profit::{Decision, ProfitGate};
+use crate::market::registry::MarketContext;
+use crate::market::validator::{Invalid, OrderValidator};
+use crate::metrics;
+use crate::model::{MatchedTrade, SpotOrder};
+use crate::util::Shutdown;
+use crate::wallet::{WalletLease, WalletPool};
+use chrono::Utc;
+use fuels::accounts::ViewOnlyAccount;
+use fuels::tx::{Receipt, TxId};
+use fuels::types::errors::Error as FuelError;
+use fuels::types::transaction::Transaction;
+use fuels::types::transaction_builders::VariableOutputPolicy;
+use fuels::types::tx_status::TxStatus;
 use fuels::types::Bits256;
-use fuels::{accounts::provider::Provider, accounts::wallet::WalletUnlocked, types::ContractId};
-use log::{error, info};
-use spark_market_sdk::SparkMarketContract;
-use sqlx::PgPool;
+use log::{debug, error, info, warn};
+use spark_market_sdk::TradeOrderEvent;
 use std::cmp::Reverse;
 use std::collections::{BinaryHeap, HashSet};
-use std::str::FromStr;
 use std::sync::Arc;
-use tokio::sync::mpsc;
-use tokio::time::Instant;
+use tokio::task::JoinSet;
+use tokio::time::{timeout_at, Duration, Instant};
+
+/// Rounds of dropping invalid orders and re-crossing before a cycle is skipped.
+const MAX_VALIDATION_ROUNDS: usize = 5;
+
+/// A set of crossing orders ready to be submitted by one pooled wallet.
+struct MatchBatch {
+    order_ids: Vec<String>,
+    bits256_ids: Vec<Bits256>,
+    total_amount: u128,
+    matches_len: usize,
+    match_time_ms: i64,
+    buy_orders: usize,
+    sell_orders: usize,
+    receive_time_ms: i64,
+}
 
 pub struct SparkMatcher {
+    pub market: Arc<MarketContext>,
     pub order_manager: Arc<OrderManager>,
-    pub market: SparkMarketContract,
-    pub log_sender: mpsc::UnboundedSender<TransactionLog>,
+    pub wallet_pool: Arc<WalletPool>,
+    /// One contract handle per pooled wallet, indexed like `WalletPool::wallets`.
+    pub contracts: Vec<Arc<MarketContract>>,
+    pub log_queue: LogQueue,
     pub last_receive_time: Arc<tokio::sync::Mutex<Instant>>,
+    pub quarantine_duration: Duration,
+    /// Quiet period to wait for more book updates after a change before matching.
+    pub debounce: Duration,
+    /// Longest time between cycles, so matching still runs without notifications.
+    pub max_wait: Duration,
+    /// Orders not reported by the indexer within this window are not matched.
+    pub freshness: Duration,
+    /// Submissions that have been sent but not completed yet.
+    pub in_flight: tokio::sync::Mutex<JoinSet<()>>,
+    /// How long shutdown waits for in-flight submissions before abandoning them.
+    pub drain_timeout: Duration,
+    /// Set when running as one of several replicas; only the leader matches.
+    pub leader: Option<Arc<LeaderElection>>,
+    pub health: Arc<HealthState>,
+    pub events: Arc<EventBus>,
+    /// Set when on-chain validation is enabled for this market.
+    pub validator: Option<OrderValidator>,
+    /// Set when the profitability gate is enabled for this market.
+    pub profit_gate: Option<Arc<ProfitGate>>,
 }
 
 impl SparkMatcher {
-    pub async fn new(order_manager: Arc<OrderManager>) -> Result<Self, Error> {
-        let provider = Provider::connect("testnet.fuel.network").await?;
-        let mnemonic = ev("MNEMONIC")?;
-        let contract_id = ev("CONTRACT_ID")?;
-        let wallet =
-            WalletUnlocked::new_from_mnemonic_phrase(&mnemonic, Some(provider.clone())).unwrap();
-        let market = SparkMarketContract::new(ContractId::from_str(&contract_id)?, wallet).await;
-
-        let database_url = ev("DATABASE_URL")?;
-        let db_pool = PgPool::connect(&database_url).await.unwrap();
+    pub async fn new(
+        market: Arc<MarketContext>,
+        wallet_pool: Arc<WalletPool>,
+        log_queue: LogQueue,
+        leader: Option<Arc<LeaderElection>>,
+        health: Arc<HealthState>,
+        events: Arc<EventBus>,
+    ) -> Result<Self, Error> {
+        let contracts = wallet_pool
+            .wallets()
+            .iter()
+            .map(|pooled| {
+                Arc::new(MarketContract::new(
+                    market.contract_id,
+                    pooled.account.clone(),
+                ))
+            })
+            .collect::<Vec<_>>();
 
-        let (log_sender, log_receiver) = mpsc::unbounded_channel();
-        tokio::spawn(log_transactions(log_receiver, db_pool));
+        let market_index = market.index;
+        let validator = market
+            .settings
+            .onchain_validation
+            .then(OrderValidator::from_env);
+        let profit_gate = match (contracts.first(), wallet_pool.wallets().first()) {
+            (Some(contract), Some(pooled)) => ProfitGate::load(
+                market_index,
+                &market.assets,
+                contract,
+                pooled.account.try_provider()?,
+            )
+            .await?
+            .map(Arc::new),
+            _ => None,
+        };
 
         Ok(Self {
-            order_manager,
+            order_manager: market.order_manager.clone(),
             market,
-            log_sender,
+            wallet_pool,
+            contracts,
+            log_queue,
             last_receive_time: Arc::new(tokio::sync::Mutex::new(Instant::now())),
+            quarantine_duration: Duration::from_secs(ev_or("QUARANTINE_SECS", 30)),
+            debounce: Duration::from_millis(ev_or("MATCHER_DEBOUNCE_MS", 50)),
+            max_wait: Duration::from_millis(ev_or("MATCHER_MAX_WAIT_MS", 1000)),
+            freshness: Duration::from_secs(ev_or("MATCHER_FRESHNESS_SECS", 60)),
+            in_flight: tokio::sync::Mutex::new(JoinSet::new()),
+            drain_timeout: Duration::from_secs(ev_or("SHUTDOWN_TIMEOUT_SECS", 30)),
+            leader,
+            health,
+            events,
+            validator,
+            profit_gate,
         })
     }
 
-    pub async fn run(&self) -> Result<(), Error> {
-        loop {
+    pub async fn run(&self, shutdown: Shutdown) -> Result<(), Error> {
+        while !shutdown.is_triggered() {
+            if let Some(leader) = self.leader.as_ref().filter(|l| !l.is_leader()) {
+                tokio::select! {
+                    _ = leader.wait_until_leader() => {}
+                    _ = shutdown.wait() => {}
+                }
+                continue;
+            }
             if let Err(e) = self.match_orders().await {
                 error!("Error during matching orders: {:?}", e);
             }
-            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            self.health.record_cycle(&self.market.id).await;
+            tokio::select! {
+                _ = self.wait_for_changes() => {}
+                _ = shutdown.wait() => {}
+            }
+        }
+        self.drain().await;
+        Ok(())
+    }
+
+    /// Waits for in-flight submissions to complete, up to `drain_timeout`.
+    async fn drain(&self) {
+        let mut in_flight = self.in_flight.lock().await;
+        info!(
+            "Market {}: waiting for {} in-flight transaction(s)",
+            self.market.id,
+            in_flight.len()
+        );
+        let drained = tokio::time::timeout(self.drain_timeout, async {
+            while in_flight.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            let pending = self.order_manager.pending_orders.read().await;
+            error!(
+                "Market {}: abandoning {} in-flight transaction(s) after {:?}, pending orders: {:?}",
+                self.market.id,
+                in_flight.len(),
+                self.drain_timeout,
+                pending
+            );
+            in_flight.abort_all();
+        }
+    }
+
+    /// Waits until the order book may cross, then keeps absorbing changes until
+    /// it has been quiet for `debounce`. Never waits longer than `max_wait`.
+    async fn wait_for_changes(&self) {
+        let deadline = Instant::now() + self.max_wait;
+        let changed = &self.order_manager.changed;
+
+        if timeout_at(deadline, changed.notified()).await.is_err() {
+            return;
+        }
+        loop {
+            let quiet_until = std::cmp::min(Instant::now() + self.debounce, deadline);
+            if timeout_at(quiet_until, changed.notified()).await.is_err() {
+                return;
+            }
         }
     }
 
+    /// Orders that may be matched this cycle: not pending, not quarantined
+    /// and refreshed by the indexer within `freshness`.
+    async fn eligible_orders(&self) -> (Vec<SpotOrder>, Vec<SpotOrder>) {
+        let excluded = self.order_manager.excluded_order_ids().await;
+        let last_seen = self.order_manager.last_seen.read().await;
+        let dust = &self.market.settings.dust;
+        let mut stale = 0;
+        let mut eligible = |order: &SpotOrder| {
+            if excluded.contains(&order.id) {
+                return false;
+            }
+            if let Some(reason) = dust.below_order_minimum(order.amount, order.price) {
+                metrics::ORDERS_BELOW_MINIMUM
+                    .with_label_values(&[&self.market.id, reason])
+                    .inc();
+                return false;
+            }
+            let fresh = last_seen
+                .get(&order.id)
+                .is_some_and(|seen| seen.elapsed() <= self.freshness);
+            if !fresh {
+                stale += 1;
+            }
+            fresh
+        };
+
+        let buy_orders: Vec<SpotOrder> = self
+            .order_manager
+            .buy_orders
+            .read()
+            .await
+            .values()
+            .flatten()
+            .filter(|o| eligible(o))
+            .cloned()
+            .collect();
+        let sell_orders: Vec<SpotOrder> = self
+            .order_manager
+            .sell_orders
+            .read()
+            .await
+            .values()
+            .flatten()
+            .filter(|o| eligible(o))
+            .cloned()
+            .collect();
+
+        if stale > 0 {
+            metrics::STALE_ORDERS_SKIPPED
+                .with_label_values(&[&self.market.id])
+                .inc_by(stale);
+        }
+        (buy_orders, sell_orders)
+    }
+
     pub async fn match_orders(&self) -> Result<(), Error> {
+        let lease = match self.wallet_pool.acquire() {
+            Some(lease) => lease,
+            None if !self.wallet_pool.can_submit() => {
+                warn!("All matcher wallets are below WALLET_MIN_BALANCE, not submitting");
+                return Ok(());
+            }
+            None => {
+                info!("All matcher wallets are busy, skipping matching cycle");
+                return Ok(());
+            }
+        };
+
         let receive_time = {
             let mut last_receive_time = self.last_receive_time.lock().await;
             let duration = last_receive_time.elapsed();
@@ -62,134 +276,594 @@ impl SparkMatcher {
             duration.as_millis() as i64
         };
 
-        info!("-----Trying to match orders");
+        info!("-----Trying to match orders on market {}", self.market.id);
 
         let match_start = Instant::now();
         info!("Match start time: {:?}", match_start);
 
-        let mut buy_queue = BinaryHeap::new();
-        let mut sell_queue = BinaryHeap::new();
+        let (buy_orders, sell_orders) = self.eligible_orders().await;
+        let contract = self.contracts[lease.wallet.index].clone();
 
-        {
-            let buy_orders = self.order_manager.buy_orders.read().await;
-            for (_, orders) in buy_orders.iter() {
-                for order in orders {
-                    buy_queue.push(order.clone());
-                }
+        let mut rejected: HashSet<String> = HashSet::new();
+        let mut rounds = 0;
+        let crossing = loop {
+            let crossing = cross(
+                buy_orders.iter().filter(|o| !rejected.contains(&o.id)),
+                sell_orders.iter().filter(|o| !rejected.contains(&o.id)),
+                &self.market.settings.dust,
+            );
+            let Some(validator) = &self.validator else {
+                break crossing;
+            };
+            if crossing.matches.is_empty() {
+                break crossing;
+            }
+            if rounds == MAX_VALIDATION_ROUNDS {
+                warn!(
+                    "Market {}: candidates still invalid after {} validation rounds, skipping cycle",
+                    self.market.id, rounds
+                );
+                return Ok(());
             }
+            rounds += 1;
 
-            let sell_orders = self.order_manager.sell_orders.read().await;
-            for (_, orders) in sell_orders.iter() {
-                for order in orders {
-                    sell_queue.push(Reverse(order.clone()));
+            let candidates = crossing.involved_orders(&buy_orders, &sell_orders);
+            let invalid = validator.invalid_orders(&contract, &candidates).await;
+            if invalid.is_empty() {
+                break crossing;
+            }
+            for (order, verdict) in invalid {
+                warn!(
+                    "Market {}: dropping order {} ({} on chain)",
+                    self.market.id,
+                    order.id,
+                    verdict.as_str()
+                );
+                metrics::ORDERS_INVALIDATED
+                    .with_label_values(&[&self.market.id, verdict.as_str()])
+                    .inc();
+                if verdict == Invalid::Missing {
+                    self.order_manager
+                        .remove_order(&order.id, order.price, order.order_type)
+                        .await;
                 }
+                rejected.insert(order.id);
+            }
+        };
+        let Crossing {
+            matches,
+            total_amount,
+            buy_remaining,
+            sell_remaining,
+            small_fills,
+            dust_remainders,
+        } = crossing;
+        if small_fills > 0 {
+            metrics::DUST_SKIPPED
+                .with_label_values(&[&self.market.id, "fill"])
+                .inc_by(small_fills);
+        }
+        if dust_remainders > 0 {
+            metrics::DUST_SKIPPED
+                .with_label_values(&[&self.market.id, "remainder"])
+                .inc_by(dust_remainders);
+        }
+
+        let match_elapsed = match_start.elapsed();
+        let match_duration = match_elapsed.as_millis() as i64;
+        info!("Match duration calculated: {}", match_duration);
+
+        let matches_len = matches.len();
+        metrics::MATCH_DURATION
+            .with_label_values(&[&self.market.id])
+            .observe(match_elapsed.as_secs_f64());
+        metrics::MATCHES_PER_CYCLE
+            .with_label_values(&[&self.market.id])
+            .observe(matches_len as f64);
+        if matches_len == 0 {
+            if let Some(gate) = &self.profit_gate {
+                gate.reset();
+            }
+            return Ok(());
+        }
+        let dust = &self.market.settings.dust;
+        let batch_notional = matches.iter().fold(0u128, |sum, t| {
+            sum.saturating_add(notional(t.amount, t.price))
+        });
+        if total_amount < dust.min_batch_amount || batch_notional < dust.min_batch_notional {
+            info!(
+                "Market {}: batch of {} matches (amount {} = {}, notional {}) is below the minimum, not submitting",
+                self.market.id,
+                matches_len,
+                total_amount,
+                self.market.assets.amount(total_amount),
+                batch_notional
+            );
+            metrics::DUST_SKIPPED
+                .with_label_values(&[&self.market.id, "batch"])
+                .inc();
+            return Ok(());
+        }
+
+        let unique_order_ids: Vec<String> = matches
+            .iter()
+            .flat_map(|trade| vec![trade.buy_order_id.clone(), trade.sell_order_id.clone()])
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .collect();
+
+        if let Some(gate) = &self.profit_gate {
+            if !self
+                .passes_profit_gate(
+                    gate,
+                    &lease,
+                    matches_len,
+                    unique_order_ids.len(),
+                    batch_notional / total_amount.max(1),
+                )
+                .await
+            {
+                return Ok(());
             }
         }
 
-        let mut matches: Vec<(String, String, u128)> = Vec::new();
-        let mut total_amount: u128 = 0;
+        let unique_bits256_ids = unique_order_ids
+            .iter()
+            .map(|id| Bits256::from_hex_str(id))
+            .collect::<Result<Vec<_>, _>>()?;
+        debug!("Market {}: matches {:?}", self.market.id, matches);
+
+        for trade in &matches {
+            self.events.publish(MarketEvent::MatchFound {
+                market: self.market.id.clone(),
+                buy_id: trade.buy_order_id.clone(),
+                sell_id: trade.sell_order_id.clone(),
+                amount: trade.amount,
+            });
+        }
+
+        self.order_manager.mark_pending(&unique_order_ids).await;
+
+        let batch = MatchBatch {
+            order_ids: unique_order_ids,
+            bits256_ids: unique_bits256_ids,
+            total_amount,
+            matches_len,
+            match_time_ms: match_duration,
+            buy_orders: buy_remaining,
+            sell_orders: sell_remaining,
+            receive_time_ms: receive_time,
+        };
+        let submission = self.submission();
+
+        let mut in_flight = self.in_flight.lock().await;
+        while in_flight.try_join_next().is_some() {}
+        in_flight.spawn(submission.run(contract, lease, batch));
+
+        Ok(())
+    }
+
+    /// Estimates the fee of submitting the batch against the matcher fees it
+    /// earns and records the decision. `price` is the batch's volume-weighted
+    /// raw price. Fails open when the fee cannot be estimated.
+    async fn passes_profit_gate(
+        &self,
+        gate: &ProfitGate,
+        lease: &WalletLease,
+        matches: usize,
+        orders: usize,
+        price: u128,
+    ) -> bool {
+        let was_deferring = gate.is_deferring();
+        let provider = match lease.wallet.account.try_provider() {
+            Ok(provider) => provider,
+            Err(e) => {
+                warn!(
+                    "No provider to estimate the transaction fee, submitting: {:?}",
+                    e
+                );
+                return true;
+            }
+        };
+        let (economics, decision) = match gate.evaluate(provider, matches, orders, price).await {
+            Ok(result) => result,
+            Err(e) => {
+                warn!(
+                    "Failed to estimate the transaction fee, submitting: {:?}",
+                    e
+                );
+                return true;
+            }
+        };
+
+        if decision != Decision::Defer || !was_deferring {
+            self.log_queue.push(LogEntry::Economics(BatchEconomicsLog {
+                market: self.market.id.clone(),
+                decided_at: Utc::now(),
+                decision: decision.as_str().to_string(),
+                matches: economics.matches,
+                orders: economics.orders,
+                estimated_gas: economics.estimated_gas,
+                gas_price: economics.gas_price,
+                estimated_fee: economics.estimated_fee,
+                estimated_fee_quote: economics.estimated_fee_quote,
+                expected_revenue: economics.expected_revenue,
+            }));
+        }
+        match decision {
+            Decision::Submit => true,
+            Decision::Defer => {
+                info!(
+                    "Market {}: deferring {} matches, fee {} (quote {}) exceeds revenue {}",
+                    self.market.id,
+                    matches,
+                    economics.estimated_fee,
+                    economics.estimated_fee_quote,
+                    economics.expected_revenue
+                );
+                metrics::BATCHES_DEFERRED
+                    .with_label_values(&[&self.market.id])
+                    .inc();
+                false
+            }
+            Decision::Force => {
+                info!(
+                    "Market {}: submitting unprofitable batch of {} matches after waiting",
+                    self.market.id, matches
+                );
+                true
+            }
+        }
+    }
+
+    fn submission(&self) -> Submission {
+        Submission {
+            market_id: self.market.id.clone(),
+            assets: self.market.assets.clone(),
+            profit_gate: self.profit_gate.clone(),
+            order_manager: self.order_manager.clone(),
+            log_queue: self.log_queue.clone(),
+            health: self.health.clone(),
+            events: self.events.clone(),
+            quarantine_duration: self.quarantine_duration,
+        }
+    }
+}
+
+/// Result of crossing the buy and sell sides of the book.
+struct Crossing {
+    matches: Vec<MatchedTrade>,
+    total_amount: u128,
+    /// Orders left on each side once nothing crosses anymore.
+    buy_remaining: usize,
+    sell_remaining: usize,
+    /// Crossing pairs skipped because the fill would be below `min_fill_amount`.
+    small_fills: u64,
+    /// Partial-fill remainders too small to be matched further.
+    dust_remainders: u64,
+}
+
+impl Crossing {
+    /// The book orders that take part in at least one match.
+    fn involved_orders(
+        &self,
+        buy_orders: &[SpotOrder],
+        sell_orders: &[SpotOrder],
+    ) -> Vec<SpotOrder> {
+        let ids: HashSet<&str> = self
+            .matches
+            .iter()
+            .flat_map(|trade| [trade.buy_order_id.as_str(), trade.sell_order_id.as_str()])
+            .collect();
+        buy_orders
+            .iter()
+            .chain(sell_orders)
+            .filter(|o| ids.contains(o.id.as_str()))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Matches the highest bids against the lowest asks until they stop crossing.
+/// Fills below `min_fill_amount` are skipped by setting the smaller order
+/// aside, and remainders that become dust are not matched further.
+fn cross<'a>(
+    buy_orders: impl Iterator<Item = &'a SpotOrder>,
+    sell_orders: impl Iterator<Item = &'a SpotOrder>,
+    dust: &DustRules,
+) -> Crossing {
+    let mut buy_queue: BinaryHeap<SpotOrder> = buy_orders.cloned().collect();
+    let mut sell_queue: BinaryHeap<Reverse<SpotOrder>> =
+        sell_orders.cloned().map(Reverse).collect();
+
+    let mut matches: Vec<MatchedTrade> = Vec::new();
+    let mut total_amount: u128 = 0;
+    let mut small_fills = 0;
+    let mut dust_remainders = 0;
 
-        while let (Some(mut buy_order), Some(Reverse(mut sell_order))) =
+    // Peek before popping, so the orders left when nothing crosses stay queued
+    // and are counted.
+    while buy_queue
+        .peek()
+        .zip(sell_queue.peek())
+        .is_some_and(|(buy, Reverse(sell))| buy.price >= sell.price)
+    {
+        let (Some(mut buy_order), Some(Reverse(mut sell_order))) =
             (buy_queue.pop(), sell_queue.pop())
-        {
-            if buy_order.price >= sell_order.price {
-                let match_amount = std::cmp::min(buy_order.amount, sell_order.amount);
-                matches.push((buy_order.id.clone(), sell_order.id.clone(), match_amount));
-                total_amount += match_amount;
+        else {
+            break;
+        };
+        let match_amount = std::cmp::min(buy_order.amount, sell_order.amount);
+        if match_amount < dust.min_fill_amount {
+            small_fills += 1;
+            if buy_order.amount <= sell_order.amount {
+                sell_queue.push(Reverse(sell_order));
+            } else {
+                buy_queue.push(buy_order);
+            }
+            continue;
+        }
+        // The order that was resting in the book sets the price.
+        let price = if buy_order.timestamp <= sell_order.timestamp {
+            buy_order.price
+        } else {
+            sell_order.price
+        };
+        matches.push(MatchedTrade {
+            buy_order_id: buy_order.id.clone(),
+            sell_order_id: sell_order.id.clone(),
+            buy_user: buy_order.user.clone(),
+            sell_user: sell_order.user.clone(),
+            price,
+            amount: match_amount,
+        });
+        total_amount += match_amount;
 
-                buy_order.amount -= match_amount;
-                sell_order.amount -= match_amount;
+        buy_order.amount -= match_amount;
+        sell_order.amount -= match_amount;
 
-                if buy_order.amount > 0 {
-                    buy_queue.push(buy_order);
-                }
+        if buy_order.amount > 0 {
+            if dust.is_dust(&buy_order) {
+                dust_remainders += 1;
+            } else {
+                buy_queue.push(buy_order);
+            }
+        }
 
-                if sell_order.amount > 0 {
-                    sell_queue.push(Reverse(sell_order));
-                }
+        if sell_order.amount > 0 {
+            if dust.is_dust(&sell_order) {
+                dust_remainders += 1;
             } else {
                 sell_queue.push(Reverse(sell_order));
             }
         }
+    }
 
-        let match_duration = match_start.elapsed().as_millis() as i64;
-        info!("Match duration calculated: {}", match_duration);
+    Crossing {
+        matches,
+        total_amount,
+        buy_remaining: buy_queue.len(),
+        sell_remaining: sell_queue.len(),
+        small_fills,
+        dust_remainders,
+    }
+}
 
-        let matches_len = matches.len();
-        if matches_len == 0 {
-            return Ok(());
+/// State a spawned submission needs, cloned out of the matcher.
+struct Submission {
+    market_id: String,
+    assets: MarketAssets,
+    profit_gate: Option<Arc<ProfitGate>>,
+    order_manager: Arc<OrderManager>,
+    log_queue: LogQueue,
+    health: Arc<HealthState>,
+    events: Arc<EventBus>,
+    quarantine_duration: Duration,
+}
+
+impl Submission {
+    async fn run(self, contract: Arc<MarketContract>, lease: WalletLease, batch: MatchBatch) {
+        let order_ids = batch.order_ids.clone();
+        self.events.publish(MarketEvent::TransactionSubmitted {
+            market: self.market_id.clone(),
+            wallet: lease.wallet.account.address().to_string(),
+            order_ids: order_ids.clone(),
+        });
+        let res = self.send(&contract, &lease, batch).await;
+        match res {
+            Ok(tx_id) => {
+                self.order_manager.remove_orders(&order_ids).await;
+                self.health
+                    .record_transaction(&self.market_id, Some(tx_id), None)
+                    .await;
+            }
+            Err(e) => {
+                error!("Error during submitting matches: {:?}", e);
+                self.events.publish(MarketEvent::TransactionFailed {
+                    market: self.market_id.clone(),
+                    order_ids: order_ids.clone(),
+                    error: e.to_string(),
+                });
+                self.health
+                    .record_transaction(&self.market_id, None, Some(e.to_string()))
+                    .await;
+                self.order_manager
+                    .quarantine(&order_ids, self.quarantine_duration)
+                    .await;
+            }
         }
+        self.order_manager.release_pending(&order_ids).await;
+        if let Err(e) = lease.wallet.refresh_balance().await {
+            error!("Failed to refresh matcher wallet balance: {:?}", e);
+        }
+    }
 
+    async fn send(
+        &self,
+        contract: &MarketContract,
+        lease: &WalletLease,
+        batch: MatchBatch,
+    ) -> Result<String, Error> {
         let post_start = Instant::now();
-        info!("Post start time: {:?}", post_start);
-
-        let unique_order_ids: HashSet<String> = matches
-            .iter()
-            .flat_map(|(buy_id, sell_id, _)| vec![buy_id.clone(), sell_id.clone()])
-            .collect();
+        let submitted_at = Utc::now();
+        info!(
+            "Post start time: {:?}, wallet #{}",
+            post_start, lease.wallet.index
+        );
 
-        let unique_bits256_ids: Vec<Bits256> = unique_order_ids
-            .iter()
-            .map(|id| Bits256::from_hex_str(id).unwrap())
-            .collect();
-        println!("=================================================");
-        println!("=================================================");
-        println!("matches {:?}", matches);
-        println!("=================================================");
-        println!("=================================================");
-        let res = self.market.match_order_many(unique_bits256_ids).await;
-        self.order_manager.clear_orders().await;
+        // The transaction is built and sent here rather than with `call()`
+        // so its id and final status are known even when it reverts.
+        let handler = contract
+            .methods()
+            .match_order_many(batch.bits256_ids)
+            .with_variable_output_policy(VariableOutputPolicy::Exactly(1));
+        let sent = async {
+            let provider = lease.wallet.account.try_provider()?;
+            let tx = handler.build_tx().await?;
+            let tx_id = tx.id(provider.chain_id());
+            let status = provider.send_transaction_and_await_commit(tx).await?;
+            Ok::<_, FuelError>((tx_id, status))
+        }
+        .await;
+        let res = match sent {
+            Ok((tx_id, status)) => {
+                let committed = committed_status(&status);
+                let gas_used = script_gas_used(&status);
+                handler
+                    .get_response_from(status)
+                    .map(|r| (r, tx_id))
+                    .map_err(|e| (e.to_string(), committed.map(|s| (tx_id, s, gas_used))))
+            }
+            Err(e) => Err((e.to_string(), None)),
+        };
+        metrics::POST_DURATION
+            .with_label_values(&[&self.market_id])
+            .observe(post_start.elapsed().as_secs_f64());
+        let outcome = if res.is_ok() { "success" } else { "failure" };
+        metrics::TRANSACTIONS
+            .with_label_values(&[&self.market_id, outcome])
+            .inc();
 
         match res {
-            Ok(r) => {
+            Ok((r, raw_tx_id)) => {
+                lease.record_success();
+                if let Some(gate) = &self.profit_gate {
+                    gate.record_gas(r.gas_used, batch.matches_len);
+                }
+                metrics::GAS_USED
+                    .with_label_values(&[&self.market_id])
+                    .inc_by(r.gas_used as f64);
                 let post_duration = post_start.elapsed().as_millis() as i64;
+                let completed_at = Utc::now();
+                let tx_id = raw_tx_id.to_string();
+                // The contract matches the ids on its own, so the fills it
+                // logged are recorded rather than the predicted crossing.
+                let trades = match r.decode_logs_with_type::<TradeOrderEvent>() {
+                    Ok(events) => events.iter().map(MatchedTrade::from).collect(),
+                    Err(e) => {
+                        warn!("Failed to decode the trades of {}: {:?}", tx_id, e);
+                        Vec::new()
+                    }
+                };
                 let log = TransactionLog {
-                    total_amount,
-                    matches_len,
-                    tx_id: r.tx_id.unwrap().to_string(),
+                    market: self.market_id.clone(),
+                    wallet: lease.wallet.account.address().to_string(),
+                    total_amount: batch.total_amount,
+                    matches_len: batch.matches_len,
+                    tx_id: tx_id.clone(),
                     gas_used: r.gas_used,
-                    match_time_ms: match_duration,
-                    buy_orders: buy_queue.len(),
-                    sell_orders: sell_queue.len(),
-                    receive_time_ms: receive_time,
+                    match_time_ms: batch.match_time_ms,
+                    buy_orders: batch.buy_orders,
+                    sell_orders: batch.sell_orders,
+                    receive_time_ms: batch.receive_time_ms,
                     post_time_ms: post_duration,
+                    submitted_at,
+                    completed_at,
+                    block_height: block_height(lease, &raw_tx_id).await,
+                    trades,
                 };
+                self.events.publish(MarketEvent::TransactionConfirmed {
+                    market: self.market_id.clone(),
+                    tx_id: tx_id.clone(),
+                    gas_used: r.gas_used,
+                });
                 info!("Logging transaction: {:?}", log);
-                self.log_sender.send(log).unwrap();
+                self.log_queue.push(LogEntry::Confirmed(log));
                 info!(
-                    "✅✅✅ Matched {} orders\nhttps://app.fuel.network/tx/0x{}/simple\n",
-                    matches_len,
-                    r.tx_id.unwrap().to_string(),
+                    "✅✅✅ Matched {} orders, volume {}\nhttps://app.fuel.network/tx/0x{}/simple\n",
+                    batch.matches_len,
+                    self.assets.amount(batch.total_amount),
+                    tx_id,
                 );
+                Ok(tx_id)
             }
-            Err(e) => {
-                error!("matching error `{}`\n", e);
-                return Err(Error::MatchOrdersError(e.to_string()));
+            Err((error, committed)) => {
+                lease.record_failure();
+                error!("matching error `{}`\n", error);
+                let transaction = match committed {
+                    Some((tx_id, status, gas_used)) => Some(SentTransaction {
+                        tx_id: tx_id.to_string(),
+                        status,
+                        gas_used,
+                        block_height: match status {
+                            TransactionStatus::SqueezedOut => None,
+                            _ => block_height(lease, &tx_id).await,
+                        },
+                    }),
+                    None => None,
+                };
+                let class = failure::classify(&error);
+                metrics::TRANSACTION_FAILURES
+                    .with_label_values(&[&self.market_id, class.reason.as_str()])
+                    .inc();
+                let log = FailedTransactionLog {
+                    market: self.market_id.clone(),
+                    wallet: lease.wallet.account.address().to_string(),
+                    order_ids: batch.order_ids,
+                    reason: class.reason,
+                    revert_code: class.revert_code,
+                    error: error.clone(),
+                    submitted_at,
+                    failed_at: Utc::now(),
+                    transaction,
+                };
+                self.log_queue.push(LogEntry::Failed(log));
+                Err(Error::MatchOrdersError(error))
             }
-        };
-
-        Ok(())
+        }
     }
+}
 
-    fn format_order_info(
-        &self,
-        buy_orders: &[(String, String, u128)],
-        sell_orders: &[(String, String, u128)],
-    ) -> String {
-        let mut logs = Vec::new();
-        logs.push("🔵 Buy Orders:".to_string());
-        for (id, price, amount) in buy_orders {
-            logs.push(format!("ID: {}, Price: {}, Amount: {}", id, price, amount));
-        }
-        logs.push("🔴 Sell Orders:".to_string());
-        for (id, price, amount) in sell_orders {
-            logs.push(format!("ID: {}, Price: {}, Amount: {}", id, price, amount));
-        }
+/// Status a committed transaction is recorded with, `None` while it is still
+/// only submitted.
+fn committed_status(status: &TxStatus) -> Option<TransactionStatus> {
+    match status {
+        TxStatus::Success { .. } => Some(TransactionStatus::Confirmed),
+        TxStatus::Revert { .. } => Some(TransactionStatus::Reverted),
+        TxStatus::SqueezedOut { .. } => Some(TransactionStatus::SqueezedOut),
+        TxStatus::Submitted => None,
+    }
+}
 
-        logs.join("\n")
+/// Gas the script used, from its result receipt.
+fn script_gas_used(status: &TxStatus) -> Option<u64> {
+    match status {
+        TxStatus::Success { receipts } | TxStatus::Revert { receipts, .. } => receipts
+            .iter()
+            .rfind(|r| matches!(r, Receipt::ScriptResult { .. }))
+            .and_then(|r| r.gas_used()),
+        _ => None,
     }
 }
 
+/// Height of the block that included `tx_id`. Best effort: history rows are
+/// written without it when the node cannot be queried.
+async fn block_height(lease: &WalletLease, tx_id: &TxId) -> Option<u32> {
+    let provider = lease.wallet.account.try_provider().ok()?;
+    let tx = provider.get_transaction_by_id(tx_id).await.ok()??;
+    tx.block_height.map(|height| *height)
+}
+
 impl OrderManager {
     pub async fn get_all_orders(&self) -> (Vec<SpotOrder>, Vec<SpotOrder>) {
         let buy_orders = self.buy_orders.read().await;
@@ -201,3 +875,126 @@ impl OrderManager {
         (buy_list, sell_list)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::OrderType;
+
+    fn order(
+        id: &str,
+        order_type: OrderType,
+        amount: u128,
+        price: u128,
+        timestamp: u64,
+    ) -> SpotOrder {
+        SpotOrder {
+            id: id.to_string(),
+            market: "0x01".to_string(),
+            user: format!("user-{}", id),
+            asset: "0x02".to_string(),
+            amount,
+            price,
+            timestamp,
+            order_type,
+        }
+    }
+
+    fn buy(id: &str, amount: u128, price: u128, timestamp: u64) -> SpotOrder {
+        order(id, OrderType::Buy, amount, price, timestamp)
+    }
+
+    fn sell(id: &str, amount: u128, price: u128, timestamp: u64) -> SpotOrder {
+        order(id, OrderType::Sell, amount, price, timestamp)
+    }
+
+    #[test]
+    fn no_cross_below_the_best_ask() {
+        let crossing = cross(
+            [buy("b", 10, 99, 1)].iter(),
+            [sell("s", 10, 100, 1)].iter(),
+            &DustRules::default(),
+        );
+        assert!(crossing.matches.is_empty());
+        assert_eq!(crossing.sell_remaining, 1);
+    }
+
+    #[test]
+    fn resting_order_sets_the_price() {
+        let crossing = cross(
+            [buy("b", 10, 105, 1)].iter(),
+            [sell("s", 10, 100, 2)].iter(),
+            &DustRules::default(),
+        );
+        assert_eq!(crossing.matches.len(), 1);
+        assert_eq!(crossing.matches[0].price, 105);
+        assert_eq!(crossing.total_amount, 10);
+
+        let crossing = cross(
+            [buy("b", 10, 105, 2)].iter(),
+            [sell("s", 10, 100, 1)].iter(),
+            &DustRules::default(),
+        );
+        assert_eq!(crossing.matches[0].price, 100);
+    }
+
+    #[test]
+    fn partial_fill_remainder_keeps_matching() {
+        let buys = [buy("b", 10, 100, 1)];
+        let sells = [sell("s1", 4, 100, 2), sell("s2", 4, 100, 3)];
+        let crossing = cross(buys.iter(), sells.iter(), &DustRules::default());
+        assert_eq!(crossing.matches.len(), 2);
+        assert!(crossing
+            .matches
+            .iter()
+            .all(|m| m.buy_order_id == "b" && m.amount == 4));
+        assert_eq!(crossing.total_amount, 8);
+        assert_eq!(crossing.buy_remaining, 1);
+        assert_eq!(crossing.sell_remaining, 0);
+    }
+
+    #[test]
+    fn dust_remainder_is_not_matched_further() {
+        let dust = DustRules {
+            min_order_amount: 5,
+            ..DustRules::default()
+        };
+        let buys = [buy("b", 10, 100, 1)];
+        let sells = [sell("s1", 7, 100, 2), sell("s2", 7, 100, 3)];
+        let crossing = cross(buys.iter(), sells.iter(), &dust);
+        assert_eq!(crossing.matches.len(), 1);
+        assert_eq!(crossing.matches[0].amount, 7);
+        assert_eq!(crossing.total_amount, 7);
+        assert_eq!(crossing.dust_remainders, 1);
+        assert_eq!(crossing.buy_remaining, 0);
+        assert_eq!(crossing.sell_remaining, 1);
+    }
+
+    #[test]
+    fn remainder_at_the_minimum_keeps_matching() {
+        let dust = DustRules {
+            min_order_amount: 3,
+            ..DustRules::default()
+        };
+        let buys = [buy("b", 10, 100, 1)];
+        let sells = [sell("s1", 7, 100, 2), sell("s2", 7, 100, 3)];
+        let crossing = cross(buys.iter(), sells.iter(), &dust);
+        assert_eq!(crossing.matches.len(), 2);
+        assert_eq!(crossing.total_amount, 10);
+        assert_eq!(crossing.dust_remainders, 0);
+    }
+
+    #[test]
+    fn small_fill_is_skipped() {
+        let dust = DustRules {
+            min_fill_amount: 5,
+            ..DustRules::default()
+        };
+        let buys = [buy("b", 3, 100, 1)];
+        let sells = [sell("s", 10, 100, 2)];
+        let crossing = cross(buys.iter(), sells.iter(), &dust);
+        assert!(crossing.matches.is_empty());
+        assert_eq!(crossing.small_fills, 1);
+        assert_eq!(crossing.sell_remaining, 1);
+    }
+}
@@ -1,3 +1,11 @@
+pub mod assets;
+pub mod contract;
+pub mod dust;
+pub mod failure;
 pub mod matcher;
+pub mod profit;
+pub mod registry;
+pub mod validator;
 
 pub use matcher::SparkMatcher;
+pub use registry::MarketRegistry;
@@ -0,0 +1,291 @@
+use std::sync::Mutex;
+
+use std::str::FromStr;
+
+use fuels::accounts::provider::Provider;
+use fuels::programs::calls::Execution;
+use tokio::time::{Duration, Instant};
+
+use crate::config::{market_ev, market_ev_or};
+use crate::error::Error;
+use crate::market::assets::MarketAssets;
+use crate::market::contract::MarketContract;
+
+/// Blocks ahead the gas price estimate should hold for.
+const GAS_PRICE_HORIZON: u32 = 10;
+/// Weight of the newest observation in the gas-per-match average.
+const GAS_EWMA_WEIGHT: f64 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Submit,
+    /// Unprofitable; wait for more matches to accumulate.
+    Defer,
+    /// Unprofitable, but deferred for longer than allowed.
+    Force,
+}
+
+impl Decision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Decision::Submit => "submitted",
+            Decision::Defer => "deferred",
+            Decision::Force => "forced",
+        }
+    }
+}
+
+/// Estimated cost and revenue of submitting one batch. The fee is paid in the
+/// chain's gas asset and also given in raw quote units, the asset the matcher
+/// fee is paid in, so the two can be compared.
+#[derive(Debug, Clone)]
+pub struct BatchEconomics {
+    pub matches: usize,
+    pub orders: usize,
+    pub estimated_gas: u64,
+    pub gas_price: u64,
+    pub estimated_fee: u128,
+    pub estimated_fee_quote: u128,
+    pub expected_revenue: u128,
+}
+
+/// How a fee in the gas asset is converted to raw quote units.
+#[derive(Debug, Clone, Copy)]
+enum GasConversion {
+    /// The market's quote asset pays for gas.
+    Quote,
+    /// The market's base asset pays for gas; converted at the batch price.
+    Base,
+    /// Raw quote units per raw gas asset unit, from `GAS_ASSET_PRICE`.
+    Fixed(f64),
+}
+
+/// Holds back batches whose transaction fee would exceed the matcher fees
+/// they earn, until enough matches accumulate or `max_defer` passes.
+pub struct ProfitGate {
+    /// The contract's matcher fee per matched order, in raw quote units.
+    matcher_fee_per_order: u128,
+    assets: MarketAssets,
+    conversion: GasConversion,
+    min_ratio: f64,
+    max_defer: Duration,
+    base_gas: u64,
+    /// Running average of gas per match, learned from confirmed transactions.
+    gas_per_match: Mutex<f64>,
+    deferred_since: Mutex<Option<Instant>>,
+}
+
+impl ProfitGate {
+    /// Returns `None` unless `PROFITABILITY_GATE` is enabled for the market.
+    /// Revenue is the matcher fee read from the contract; enabling the gate
+    /// fails when the contract pays no fee or the gas asset cannot be priced
+    /// in the quote asset.
+    pub async fn load(
+        index: usize,
+        assets: &MarketAssets,
+        contract: &MarketContract,
+        provider: &Provider,
+    ) -> Result<Option<Self>, Error> {
+        if !market_ev_or(index, "PROFITABILITY_GATE", false) {
+            return Ok(None);
+        }
+        let matcher_fee = contract
+            .methods()
+            .matcher_fee()
+            .simulate(Execution::StateReadOnly)
+            .await
+            .map_err(|e| {
+                Error::MarketMetadataError(format!(
+                    "failed to read the matcher fee of market #{}: {:?}",
+                    index, e
+                ))
+            })?
+            .value;
+        if matcher_fee == 0 {
+            return Err(Error::MarketMetadataError(format!(
+                "market #{} pays no matcher fee, the profitability gate would defer every batch",
+                index
+            )));
+        }
+
+        let gas_asset = *provider.consensus_parameters().base_asset_id();
+        let conversion = if gas_asset == assets.quote_asset {
+            GasConversion::Quote
+        } else if gas_asset == assets.base_asset {
+            GasConversion::Base
+        } else {
+            let price = market_ev(index, "GAS_ASSET_PRICE").map_err(|_| {
+                Error::MarketMetadataError(format!(
+                    "market #{} does not trade the gas asset 0x{}; set GAS_ASSET_PRICE to price it in the quote asset",
+                    index, gas_asset
+                ))
+            })?;
+            let price = f64::from_str(price.trim()).map_err(|e| {
+                Error::MarketMetadataError(format!(
+                    "invalid GAS_ASSET_PRICE for market #{}: {:?}",
+                    index, e
+                ))
+            })?;
+            let gas_decimals: i32 = market_ev_or(index, "GAS_ASSET_DECIMALS", 9);
+            GasConversion::Fixed(price * 10f64.powi(assets.quote_decimals as i32 - gas_decimals))
+        };
+
+        Ok(Some(Self {
+            matcher_fee_per_order: matcher_fee.into(),
+            assets: assets.clone(),
+            conversion,
+            min_ratio: market_ev_or(index, "MIN_PROFIT_RATIO", 1.0),
+            max_defer: Duration::from_secs(market_ev_or(index, "PROFIT_MAX_DEFER_SECS", 30)),
+            base_gas: market_ev_or(index, "GAS_BASE_ESTIMATE", 50_000),
+            gas_per_match: Mutex::new(market_ev_or(index, "GAS_PER_MATCH_ESTIMATE", 150_000.0)),
+            deferred_since: Mutex::new(None),
+        }))
+    }
+
+    /// `price` is the raw price the batch trades at, used when the base asset
+    /// pays for gas.
+    pub async fn evaluate(
+        &self,
+        provider: &Provider,
+        matches: usize,
+        orders: usize,
+        price: u128,
+    ) -> Result<(BatchEconomics, Decision), Error> {
+        let gas_per_match = *self.gas_per_match.lock().unwrap_or_else(|e| e.into_inner());
+        let estimated_gas = self.base_gas + (gas_per_match * matches as f64).ceil() as u64;
+        let gas_price = provider
+            .estimate_gas_price(GAS_PRICE_HORIZON)
+            .await?
+            .gas_price;
+        let gas_price_factor = provider
+            .consensus_parameters()
+            .fee_params()
+            .gas_price_factor()
+            .max(1);
+        let estimated_fee = (u128::from(estimated_gas) * u128::from(gas_price))
+            .div_ceil(u128::from(gas_price_factor));
+        let estimated_fee_quote = self.to_quote(estimated_fee, price);
+        let expected_revenue = self.matcher_fee_per_order * orders as u128;
+
+        let economics = BatchEconomics {
+            matches,
+            orders,
+            estimated_gas,
+            gas_price,
+            estimated_fee,
+            estimated_fee_quote,
+            expected_revenue,
+        };
+        let profitable = expected_revenue as f64 >= estimated_fee_quote as f64 * self.min_ratio;
+
+        let mut deferred_since = self
+            .deferred_since
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let decision = if profitable {
+            Decision::Submit
+        } else {
+            match *deferred_since {
+                Some(since) if since.elapsed() >= self.max_defer => Decision::Force,
+                Some(_) => Decision::Defer,
+                None => {
+                    *deferred_since = Some(Instant::now());
+                    Decision::Defer
+                }
+            }
+        };
+        if decision != Decision::Defer {
+            *deferred_since = None;
+        }
+        Ok((economics, decision))
+    }
+
+    /// Converts a raw gas asset amount to raw quote units, rounding up.
+    fn to_quote(&self, fee: u128, price: u128) -> u128 {
+        let rate = match self.conversion {
+            GasConversion::Quote => return fee,
+            GasConversion::Base => {
+                let assets = &self.assets;
+                price as f64
+                    * 10f64.powi(
+                        assets.quote_decimals as i32
+                            - assets.base_decimals as i32
+                            - assets.price_decimals as i32,
+                    )
+            }
+            GasConversion::Fixed(rate) => rate,
+        };
+        (fee as f64 * rate).ceil() as u128
+    }
+
+    /// Whether a deferral is already running, to avoid recording every cycle.
+    pub fn is_deferring(&self) -> bool {
+        self.deferred_since
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some()
+    }
+
+    /// Folds the gas of a confirmed transaction into the per-match estimate.
+    pub fn record_gas(&self, gas_used: u64, matches: usize) {
+        if matches == 0 {
+            return;
+        }
+        let observed = gas_used.saturating_sub(self.base_gas) as f64 / matches as f64;
+        let mut gas_per_match = self.gas_per_match.lock().unwrap_or_else(|e| e.into_inner());
+        *gas_per_match = GAS_EWMA_WEIGHT * observed + (1.0 - GAS_EWMA_WEIGHT) * *gas_per_match;
+    }
+
+    /// Ends a deferral once nothing is left to match.
+    pub fn reset(&self) {
+        *self
+            .deferred_since
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fuels::types::AssetId;
+
+    use super::*;
+
+    fn gate(conversion: GasConversion) -> ProfitGate {
+        ProfitGate {
+            matcher_fee_per_order: 1,
+            assets: MarketAssets {
+                base_asset: AssetId::zeroed(),
+                quote_asset: AssetId::zeroed(),
+                base_decimals: 9,
+                quote_decimals: 6,
+                price_decimals: 9,
+            },
+            conversion,
+            min_ratio: 1.0,
+            max_defer: Duration::from_secs(30),
+            base_gas: 0,
+            gas_per_match: Mutex::new(0.0),
+            deferred_since: Mutex::new(None),
+        }
+    }
+
+    #[test]
+    fn quote_gas_is_not_converted() {
+        assert_eq!(gate(GasConversion::Quote).to_quote(12_345, 0), 12_345);
+    }
+
+    #[test]
+    fn base_gas_is_converted_at_the_batch_price() {
+        // 0.001 base at 3000 quote per base is 3 quote, 3_000_000 raw.
+        let fee = 1_000_000;
+        let price = 3_000 * 1_000_000_000;
+        assert_eq!(gate(GasConversion::Base).to_quote(fee, price), 3_000_000);
+    }
+
+    #[test]
+    fn fixed_rate_rounds_up() {
+        // 1 raw gas unit at 2.5 raw quote units.
+        assert_eq!(gate(GasConversion::Fixed(2.5)).to_quote(1, 0), 3);
+    }
+}
@@ -0,0 +1,213 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use fuels::types::{AssetId, ContractId};
+use log::info;
+
+use crate::config::{ev, market_ev_or};
+use crate::error::Error;
+use crate::events::EventBus;
+use crate::management::manager::OrderManager;
+use crate::market::assets::MarketAssets;
+use crate::market::contract;
+use crate::market::dust::DustRules;
+use crate::model::spot_order::SpotOrderIndexer;
+use crate::wallet::SignerAccount;
+
+/// Per-market state: the contract the matcher submits to and its order book.
+pub struct MarketContext {
+    pub index: usize,
+    /// Contract id as configured, used in indexer queries and REST paths.
+    pub id: String,
+    pub contract_id: ContractId,
+    pub order_manager: Arc<OrderManager>,
+    pub settings: MarketSettings,
+    pub assets: MarketAssets,
+}
+
+impl MarketContext {
+    /// Why an indexer order does not belong to this market, if it does not:
+    /// it names another market, or is not denominated in the base asset.
+    /// Fields the indexer leaves out are not checked.
+    pub fn foreign_order_reason(&self, order: &SpotOrderIndexer) -> Option<&'static str> {
+        if let Some(market) = &order.market {
+            if normalize_id(market) != normalize_id(&self.id) {
+                return Some("wrong_market");
+            }
+        }
+        if AssetId::from_str(&order.asset).ok() != Some(self.assets.base_asset) {
+            return Some("wrong_asset");
+        }
+        if let Some(asset_type) = &order.asset_type {
+            if !asset_type.eq_ignore_ascii_case("base") {
+                return Some("wrong_asset_type");
+            }
+        }
+        None
+    }
+}
+
+/// Options that can differ between markets. Each one is read from
+/// `MARKET_<index>_<KEY>` and falls back to the global `<KEY>`.
+#[derive(Debug, Clone)]
+pub struct MarketSettings {
+    /// Check candidate orders against the contract before submitting.
+    pub onchain_validation: bool,
+    pub dust: DustRules,
+}
+
+impl MarketSettings {
+    fn from_env(index: usize) -> Self {
+        Self {
+            onchain_validation: market_ev_or(index, "ONCHAIN_VALIDATION", false),
+            dust: DustRules::from_env(index),
+        }
+    }
+}
+
+/// All markets served by this process, loaded from the comma-separated
+/// `MARKETS` list (or the single `CONTRACT_ID` when it is not set).
+pub struct MarketRegistry {
+    markets: Vec<Arc<MarketContext>>,
+}
+
+impl MarketRegistry {
+    /// `account` is only used to read each market's config from its contract.
+    pub async fn from_env(
+        events: Arc<EventBus>,
+        account: &SignerAccount,
+    ) -> Result<Arc<Self>, Error> {
+        let ids = ev("MARKETS").or_else(|_| ev("CONTRACT_ID"))?;
+        let mut markets = Vec::new();
+        for (index, id) in ids
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .enumerate()
+        {
+            info!("Registering market #{}: {}", index, id);
+            let contract_id = ContractId::from_str(id)?;
+            let contract = contract::connect(contract_id, account.clone()).await?;
+            markets.push(Arc::new(MarketContext {
+                index,
+                id: id.to_string(),
+                contract_id,
+                order_manager: OrderManager::new(id.to_string(), events.clone()),
+                settings: MarketSettings::from_env(index),
+                assets: MarketAssets::load(index, &contract).await?,
+            }));
+        }
+        if markets.is_empty() {
+            return Err(Error::StringParsingError(
+                "No markets configured in MARKETS or CONTRACT_ID".to_string(),
+            ));
+        }
+        Ok(Arc::new(Self { markets }))
+    }
+
+    pub fn markets(&self) -> &[Arc<MarketContext>] {
+        &self.markets
+    }
+
+    pub fn by_index(&self, index: usize) -> Option<&Arc<MarketContext>> {
+        self.markets.get(index)
+    }
+
+    /// Looks a market up by contract id, with or without the `0x` prefix.
+    pub fn get(&self, id: &str) -> Option<&Arc<MarketContext>> {
+        let id = normalize_id(id);
+        self.markets.iter().find(|m| normalize_id(&m.id) == id)
+    }
+}
+
+fn normalize_id(id: &str) -> String {
+    id.trim_start_matches("0x").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::OrderType;
+
+    const MARKET: &str = "0x0101010101010101010101010101010101010101010101010101010101010101";
+
+    fn context() -> MarketContext {
+        MarketContext {
+            index: 0,
+            id: MARKET.to_string(),
+            contract_id: ContractId::from_str(MARKET).unwrap(),
+            order_manager: OrderManager::new(MARKET.to_string(), EventBus::new(16)),
+            settings: MarketSettings {
+                onchain_validation: false,
+                dust: DustRules::default(),
+            },
+            assets: MarketAssets {
+                base_asset: AssetId::new([2; 32]),
+                quote_asset: AssetId::new([3; 32]),
+                base_decimals: 8,
+                quote_decimals: 6,
+                price_decimals: 9,
+            },
+        }
+    }
+
+    fn order() -> SpotOrderIndexer {
+        SpotOrderIndexer {
+            id: "0x04".to_string(),
+            market: Some(MARKET.to_string()),
+            user: "0x05".to_string(),
+            asset: format!("0x{}", AssetId::new([2; 32])),
+            amount: "1".to_string(),
+            price: "1".to_string(),
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            order_type: OrderType::Buy,
+            status: None,
+            asset_type: Some("Base".to_string()),
+            db_write_timestamp: None,
+            initial_amount: None,
+        }
+    }
+
+    #[test]
+    fn accepts_orders_of_the_market() {
+        let context = context();
+        assert_eq!(context.foreign_order_reason(&order()), None);
+
+        // Ids are compared without prefix or case, and missing fields pass.
+        let mut order = order();
+        order.market = Some(MARKET.trim_start_matches("0x").to_uppercase());
+        order.asset_type = None;
+        assert_eq!(context.foreign_order_reason(&order), None);
+    }
+
+    #[test]
+    fn rejects_orders_of_another_market() {
+        let mut order = order();
+        order.market = Some(format!("0x{}", "09".repeat(32)));
+        assert_eq!(context().foreign_order_reason(&order), Some("wrong_market"));
+    }
+
+    #[test]
+    fn rejects_orders_not_in_the_base_asset() {
+        let context = context();
+        let mut order = order();
+        order.asset = format!("0x{}", context.assets.quote_asset);
+        assert_eq!(context.foreign_order_reason(&order), Some("wrong_asset"));
+
+        order.asset = format!("0x{}", AssetId::new([9; 32]));
+        assert_eq!(context.foreign_order_reason(&order), Some("wrong_asset"));
+
+        order.asset = "not an asset".to_string();
+        assert_eq!(context.foreign_order_reason(&order), Some("wrong_asset"));
+    }
+
+    #[test]
+    fn rejects_orders_with_another_asset_type() {
+        let mut order = order();
+        order.asset_type = Some("Quote".to_string());
+        assert_eq!(
+            context().foreign_order_reason(&order),
+            Some("wrong_asset_type")
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use fuels::programs::calls::Execution;
+use fuels::types::Bits256;
+use futures_util::stream::{self, StreamExt};
+use log::warn;
+use tokio::time::{Duration, Instant};
+
+use crate::config::ev_or;
+use crate::market::contract::MarketContract;
+use crate::model::SpotOrder;
+
+/// Remaining amount and price of an order as stored in the market contract.
+#[derive(Debug, Clone, Copy)]
+struct OnChainOrder {
+    amount: u128,
+    price: u128,
+}
+
+/// Why a candidate order was dropped before submission.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Invalid {
+    /// The contract no longer has the order: filled or cancelled.
+    Missing,
+    /// The contract has a different price or less remaining amount.
+    Mismatch,
+}
+
+impl Invalid {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Invalid::Missing => "missing",
+            Invalid::Mismatch => "mismatch",
+        }
+    }
+}
+
+/// Reads candidate orders from the contract before they are submitted, to
+/// catch orders the indexer has not caught up on. Reads run concurrently and
+/// results are cached briefly, since the same orders show up cycle after cycle.
+pub struct OrderValidator {
+    cache: Mutex<HashMap<String, (Instant, Option<OnChainOrder>)>>,
+    cache_ttl: Duration,
+    concurrency: usize,
+}
+
+impl OrderValidator {
+    pub fn from_env() -> Self {
+        Self {
+            cache: Mutex::new(HashMap::new()),
+            cache_ttl: Duration::from_millis(ev_or("ONCHAIN_CACHE_MS", 2000)),
+            concurrency: ev_or("ONCHAIN_VALIDATION_CONCURRENCY", 16usize).max(1),
+        }
+    }
+
+    /// Orders whose on-chain state does not match the book. Orders that could
+    /// not be read are let through, so a flaky node does not stop matching.
+    pub async fn invalid_orders(
+        &self,
+        contract: &MarketContract,
+        orders: &[SpotOrder],
+    ) -> Vec<(SpotOrder, Invalid)> {
+        let mut states = HashMap::new();
+        let mut to_fetch = Vec::new();
+        {
+            let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+            cache.retain(|_, (at, _)| at.elapsed() <= self.cache_ttl);
+            for order in orders {
+                match cache.get(&order.id) {
+                    Some((_, state)) => {
+                        states.insert(order.id.clone(), *state);
+                    }
+                    None => to_fetch.push(order.id.clone()),
+                }
+            }
+        }
+
+        let fetched: Vec<(String, Option<Option<OnChainOrder>>)> = stream::iter(to_fetch)
+            .map(|id| async move {
+                let state = fetch(contract, &id).await;
+                (id, state)
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+        {
+            let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+            for (id, state) in fetched {
+                if let Some(state) = state {
+                    cache.insert(id.clone(), (Instant::now(), state));
+                    states.insert(id, state);
+                }
+            }
+        }
+
+        orders
+            .iter()
+            .filter_map(|order| {
+                let verdict = match states.get(&order.id)? {
+                    None => Invalid::Missing,
+                    Some(on_chain)
+                        if on_chain.price != order.price || on_chain.amount < order.amount =>
+                    {
+                        Invalid::Mismatch
+                    }
+                    Some(_) => return None,
+                };
+                Some((order.clone(), verdict))
+            })
+            .collect()
+    }
+}
+
+/// `None` when the read failed, `Some(None)` when the order does not exist.
+async fn fetch(contract: &MarketContract, id: &str) -> Option<Option<OnChainOrder>> {
+    let order_id = match Bits256::from_hex_str(id) {
+        Ok(order_id) => order_id,
+        Err(e) => {
+            warn!("Invalid order id {}: {:?}", id, e);
+            return None;
+        }
+    };
+    match contract
+        .methods()
+        .order(order_id)
+        .simulate(Execution::StateReadOnly)
+        .await
+    {
+        Ok(response) => Some(response.value.map(|order| OnChainOrder {
+            amount: order.amount.into(),
+            price: order.price.into(),
+        })),
+        Err(e) => {
+            warn!("Failed to read order {} from the contract: {:?}", id, e);
+            None
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::sync::LazyLock;
+
+use prometheus::{
+    register_counter_vec, register_gauge_vec, register_histogram_vec, register_int_counter,
+    register_int_counter_vec, register_int_gauge_vec, CounterVec, Encoder, GaugeVec, HistogramVec,
+    IntCounter, IntCounterVec, IntGaugeVec, TextEncoder,
+};
+
+use crate::market::MarketRegistry;
+use crate::wallet::WalletPool;
+
+pub static BOOK_DEPTH: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "matcher_book_orders",
+        "Orders in the order book",
+        &["market", "side"]
+    )
+    .unwrap()
+});
+
+pub static PENDING_ORDERS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "matcher_pending_orders",
+        "Orders in transactions that have not completed yet",
+        &["market"]
+    )
+    .unwrap()
+});
+
+pub static QUARANTINED_ORDERS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "matcher_quarantined_orders",
+        "Orders held back after a failed transaction",
+        &["market"]
+    )
+    .unwrap()
+});
+
+pub static ORDERS_INGESTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "matcher_orders_ingested_total",
+        "Orders received from the indexer",
+        &["market", "side"]
+    )
+    .unwrap()
+});
+
+pub static ORDERS_REJECTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "matcher_orders_rejected_total",
+        "Orders from the indexer that were rejected on ingest",
+        &["market", "reason"]
+    )
+    .unwrap()
+});
+
+pub static ORDERS_EVICTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "matcher_orders_evicted_total",
+        "Orders removed after the indexer stopped reporting them",
+        &["market"]
+    )
+    .unwrap()
+});
+
+pub static STALE_ORDERS_SKIPPED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "matcher_stale_orders_skipped_total",
+        "Orders left out of a matching cycle because they were not refreshed recently",
+        &["market"]
+    )
+    .unwrap()
+});
+
+pub static ORDERS_INVALIDATED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "matcher_orders_invalidated_total",
+        "Candidate orders dropped by on-chain validation",
+        &["market", "reason"]
+    )
+    .unwrap()
+});
+
+pub static ORDERS_BELOW_MINIMUM: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "matcher_orders_below_minimum_total",
+        "Times an order was left out of a cycle for being below MIN_ORDER_AMOUNT or MIN_ORDER_NOTIONAL",
+        &["market", "reason"]
+    )
+    .unwrap()
+});
+
+pub static DUST_SKIPPED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "matcher_dust_skipped_total",
+        "Fills, partial-fill remainders and batches skipped as dust",
+        &["market", "kind"]
+    )
+    .unwrap()
+});
+
+pub static BATCHES_DEFERRED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "matcher_batches_deferred_total",
+        "Cycles whose batch was held back as unprofitable",
+        &["market"]
+    )
+    .unwrap()
+});
+
+pub static MATCH_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "matcher_match_duration_seconds",
+        "Time spent finding crossing orders in one cycle",
+        &["market"],
+        vec![0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0]
+    )
+    .unwrap()
+});
+
+pub static POST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "matcher_post_duration_seconds",
+        "Time from submitting match_order_many until it completed",
+        &["market"],
+        vec![0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0]
+    )
+    .unwrap()
+});
+
+pub static MATCHES_PER_CYCLE: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "matcher_matches_per_cycle",
+        "Crossing pairs found per matching cycle",
+        &["market"],
+        vec![0.0, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0]
+    )
+    .unwrap()
+});
+
+pub static GAS_USED: LazyLock<CounterVec> = LazyLock::new(|| {
+    register_counter_vec!(
+        "matcher_gas_used_total",
+        "Gas used by successful match transactions",
+        &["market"]
+    )
+    .unwrap()
+});
+
+pub static TRANSACTIONS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "matcher_transactions_total",
+        "Submitted match transactions by outcome",
+        &["market", "outcome"]
+    )
+    .unwrap()
+});
+
+pub static TRANSACTION_FAILURES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "matcher_transaction_failures_total",
+        "Failed match transactions by classified reason",
+        &["market", "reason"]
+    )
+    .unwrap()
+});
+
+pub static TRANSACTION_LOG_SPILLED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "matcher_transaction_log_spilled_total",
+        "Transaction log entries written to the local spill file"
+    )
+    .unwrap()
+});
+
+pub static WEBSOCKET_RECONNECTS: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "matcher_websocket_reconnects_total",
+        "Indexer WebSocket (re)connection attempts"
+    )
+    .unwrap()
+});
+
+pub static WALLET_BALANCE: LazyLock<GaugeVec> = LazyLock::new(|| {
+    register_gauge_vec!(
+        "matcher_wallet_balance",
+        "Base asset balance of a matcher wallet",
+        &["wallet"]
+    )
+    .unwrap()
+});
+
+pub static WALLET_IN_FLIGHT: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "matcher_wallet_in_flight",
+        "Transactions in flight per matcher wallet",
+        &["wallet"]
+    )
+    .unwrap()
+});
+
+/// Refreshes gauges that are sampled from shared state and encodes every
+/// registered metric in the Prometheus text format.
+pub async fn render(registry: &MarketRegistry, pool: &WalletPool) -> String {
+    for market in registry.markets() {
+        let manager = &market.order_manager;
+        let buy_orders = manager.buy_orders.read().await;
+        let sell_orders = manager.sell_orders.read().await;
+        BOOK_DEPTH
+            .with_label_values(&[&market.id, "buy"])
+            .set(buy_orders.values().map(Vec::len).sum::<usize>() as i64);
+        BOOK_DEPTH
+            .with_label_values(&[&market.id, "sell"])
+            .set(sell_orders.values().map(Vec::len).sum::<usize>() as i64);
+        drop(buy_orders);
+        drop(sell_orders);
+
+        PENDING_ORDERS
+            .with_label_values(&[&market.id])
+            .set(manager.pending_orders.read().await.len() as i64);
+        QUARANTINED_ORDERS
+            .with_label_values(&[&market.id])
+            .set(manager.quarantined_orders.read().await.len() as i64);
+    }
+
+    for wallet in pool.status() {
+        WALLET_BALANCE
+            .with_label_values(&[&wallet.address])
+            .set(wallet.balance as f64);
+        WALLET_IN_FLIGHT
+            .with_label_values(&[&wallet.address])
+            .set(wallet.in_flight as i64);
+    }
+
+    let mut buffer = Vec::new();
+    let encoder = TextEncoder::new();
+    if let Err(e) = encoder.encode(&prometheus::gather(), &mut buffer) {
+        log::error!("Failed to encode metrics: {:?}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
@@ -0,0 +1,91 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Orders resting at one price, aggregated.
+#[derive(Debug, Clone, JsonSchema, Serialize)]
+pub struct PriceLevel {
+    pub price: u128,
+    pub total_amount: u128,
+    pub order_count: usize,
+}
+
+#[derive(Debug, Clone, JsonSchema, Serialize)]
+pub struct BookDepth {
+    /// Best (highest) bid first.
+    pub bids: Vec<PriceLevel>,
+    /// Best (lowest) ask first.
+    pub asks: Vec<PriceLevel>,
+}
+
+#[derive(Debug, Clone, JsonSchema, Serialize)]
+pub struct TopOfBook {
+    pub best_bid: Option<u128>,
+    pub best_ask: Option<u128>,
+    /// `best_ask - best_bid`; negative while the book is crossed.
+    pub spread: Option<i128>,
+    pub mid: Option<u128>,
+}
+
+impl TopOfBook {
+    pub fn new(best_bid: Option<u128>, best_ask: Option<u128>) -> Self {
+        let (spread, mid) = match (best_bid, best_ask) {
+            (Some(bid), Some(ask)) => (
+                Some(ask as i128 - bid as i128),
+                Some(bid / 2 + ask / 2 + (bid % 2 + ask % 2) / 2),
+            ),
+            _ => (None, None),
+        };
+        Self {
+            best_bid,
+            best_ask,
+            spread,
+            mid,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_sided_book_has_no_spread() {
+        let top = TopOfBook::new(Some(100), None);
+        assert_eq!(top.spread, None);
+        assert_eq!(top.mid, None);
+        assert_eq!(TopOfBook::new(None, Some(100)).mid, None);
+    }
+
+    #[test]
+    fn spread_and_mid() {
+        let top = TopOfBook::new(Some(100), Some(110));
+        assert_eq!(top.spread, Some(10));
+        assert_eq!(top.mid, Some(105));
+    }
+
+    #[test]
+    fn locked_book_has_zero_spread() {
+        let top = TopOfBook::new(Some(100), Some(100));
+        assert_eq!(top.spread, Some(0));
+        assert_eq!(top.mid, Some(100));
+    }
+
+    #[test]
+    fn crossed_book_has_negative_spread() {
+        let top = TopOfBook::new(Some(105), Some(100));
+        assert_eq!(top.spread, Some(-5));
+        assert_eq!(top.mid, Some(102));
+    }
+
+    #[test]
+    fn mid_rounds_down() {
+        assert_eq!(TopOfBook::new(Some(1), Some(2)).mid, Some(1));
+        assert_eq!(TopOfBook::new(Some(3), Some(5)).mid, Some(4));
+    }
+
+    #[test]
+    fn mid_does_not_overflow() {
+        let top = TopOfBook::new(Some(u128::MAX), Some(u128::MAX));
+        assert_eq!(top.mid, Some(u128::MAX));
+    }
+}
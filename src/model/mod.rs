@@ -1,3 +1,7 @@
+pub mod book;
 pub mod spot_order;
+pub mod trade;
 
+pub use book::{BookDepth, PriceLevel, TopOfBook};
 pub use spot_order::{OrderType, SpotOrder};
+pub use trade::MatchedTrade;
@@ -1,6 +1,5 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, JsonSchema, Serialize, Deserialize)]
 pub enum OrderType {
@@ -11,6 +10,7 @@ pub enum OrderType {
 #[derive(Debug, Clone, JsonSchema, Serialize, Deserialize, Eq)]
 pub struct SpotOrder {
     pub id: String,
+    pub market: String,
     pub user: String,
     pub asset: String,
     pub amount: u128,
@@ -38,8 +38,10 @@ impl PartialOrd for SpotOrder {
 }
 
 #[derive(Debug, Clone, Deserialize)]
+#[allow(dead_code)]
 pub struct SpotOrderIndexer {
     pub id: String,
+    pub market: Option<String>,
     pub user: String,
     pub asset: String,
     pub amount: String,
@@ -55,6 +57,7 @@ pub struct SpotOrderIndexer {
 impl SpotOrder {
     pub fn from_indexer(
         intermediate: SpotOrderIndexer,
+        market: &str,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let amount = intermediate.amount.parse::<u128>()?;
         let price = intermediate.price.parse::<u128>()?;
@@ -63,6 +66,7 @@ impl SpotOrder {
 
         Ok(SpotOrder {
             id: intermediate.id,
+            market: market.to_string(),
             user: intermediate.user,
             asset: intermediate.asset,
             amount,
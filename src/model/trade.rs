@@ -0,0 +1,45 @@
+use fuels::types::{Bits256, Identity};
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr, PickFirst};
+use spark_market_sdk::TradeOrderEvent;
+
+/// One fill: a crossing pair predicted by the matcher, or a trade the
+/// contract executed, decoded from its `TradeOrderEvent` log. Amounts are
+/// serialized as strings, see [`TransactionLog`](crate::logger::TransactionLog).
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchedTrade {
+    pub buy_order_id: String,
+    pub sell_order_id: String,
+    pub buy_user: String,
+    pub sell_user: String,
+    #[serde_as(as = "PickFirst<(DisplayFromStr, _)>")]
+    pub price: u128,
+    #[serde_as(as = "PickFirst<(DisplayFromStr, _)>")]
+    pub amount: u128,
+}
+
+impl From<&TradeOrderEvent> for MatchedTrade {
+    fn from(event: &TradeOrderEvent) -> Self {
+        Self {
+            buy_order_id: order_id(&event.base_buy_order_id),
+            sell_order_id: order_id(&event.base_sell_order_id),
+            buy_user: user(&event.order_buyer),
+            sell_user: user(&event.order_seller),
+            price: event.trade_price.into(),
+            amount: event.trade_size.into(),
+        }
+    }
+}
+
+/// Order ids in the indexer's `0x`-prefixed hex form.
+fn order_id(id: &Bits256) -> String {
+    format!("0x{}", hex::encode(id.0))
+}
+
+fn user(identity: &Identity) -> String {
+    match identity {
+        Identity::Address(address) => format!("{:#x}", address),
+        Identity::ContractId(contract_id) => format!("{:#x}", contract_id),
+    }
+}
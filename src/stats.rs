@@ -0,0 +1,170 @@
+use chrono::{Duration as ChronoDuration, Utc};
+use log::{error, info};
+use rocket::FromFormField;
+use schemars::JsonSchema;
+use serde::Serialize;
+use sqlx::PgPool;
+use tokio::time::{self, Duration};
+
+use crate::config::ev_or;
+use crate::error::Error;
+
+/// Granularity of the `transaction_stats_rollup` time series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema, FromFormField)]
+pub enum StatsBucket {
+    #[serde(rename = "1m")]
+    #[field(value = "1m")]
+    Minute,
+    #[serde(rename = "1h")]
+    #[field(value = "1h")]
+    Hour,
+    #[serde(rename = "1d")]
+    #[field(value = "1d")]
+    Day,
+}
+
+impl StatsBucket {
+    pub const ALL: [StatsBucket; 3] = [StatsBucket::Minute, StatsBucket::Hour, StatsBucket::Day];
+
+    /// Value stored in the `bucket` column.
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatsBucket::Minute => "1m",
+            StatsBucket::Hour => "1h",
+            StatsBucket::Day => "1d",
+        }
+    }
+
+    /// Field name for Postgres `date_trunc`.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            StatsBucket::Minute => "minute",
+            StatsBucket::Hour => "hour",
+            StatsBucket::Day => "day",
+        }
+    }
+}
+
+/// Days of raw `transaction_stats` rows (and minute buckets) that are kept.
+pub fn retention_days() -> i32 {
+    ev_or("STATS_RETENTION_DAYS", 7)
+}
+
+const ROLLUP_OVERLAP: ChronoDuration = ChronoDuration::minutes(1);
+
+/// Periodically folds raw `transaction_stats` rows into per-bucket rollups
+/// and prunes raw rows (and minute buckets) older than the retention period.
+pub struct StatsRollup {
+    db_pool: PgPool,
+    interval: Duration,
+    retention_days: i32,
+}
+
+impl StatsRollup {
+    pub fn from_env(db_pool: PgPool) -> Self {
+        Self {
+            db_pool,
+            interval: Duration::from_secs(ev_or("STATS_ROLLUP_INTERVAL_SECS", 60)),
+            retention_days: retention_days(),
+        }
+    }
+
+    pub async fn run(&self) -> Result<(), Error> {
+        // Buckets are recomputed from whole raw days, so the first pass
+        // starts at the oldest day that has not been pruned.
+        let mut since = Utc::now() - ChronoDuration::days(self.retention_days.into());
+        let mut ticker = time::interval(self.interval);
+        loop {
+            ticker.tick().await;
+            // Overlap passes so rows from writes still in flight are not missed;
+            // recomputing a bucket twice is harmless.
+            let started = Utc::now() - ROLLUP_OVERLAP;
+            match self.rollup(since).await {
+                Ok(()) => since = started,
+                Err(e) => error!("Failed to roll up transaction stats: {:?}", e),
+            }
+            match self.prune().await {
+                Ok(0) => {}
+                Ok(rows) => info!("Pruned {} raw transaction stats rows", rows),
+                Err(e) => error!("Failed to prune transaction stats: {:?}", e),
+            }
+        }
+    }
+
+    /// Recomputes every bucket that contains rows recorded since `since`. Rows
+    /// are bucketed by completion time, which may be long before they were
+    /// written when they are replayed from the spill file; buckets from days
+    /// that were already pruned are left alone, as their raw rows are gone.
+    async fn rollup(&self, since: chrono::DateTime<Utc>) -> Result<(), sqlx::Error> {
+        for bucket in StatsBucket::ALL {
+            sqlx::query!(
+                r#"
+                INSERT INTO transaction_stats_rollup
+                    (bucket, bucket_start, market, transactions, matches, volume, gas_used, match_time_ms, receive_time_ms, post_time_ms, buy_orders, sell_orders)
+                SELECT
+                    $1,
+                    date_trunc($2, created_at),
+                    COALESCE(market, ''),
+                    COUNT(*),
+                    COALESCE(SUM(total_transactions), 0),
+                    COALESCE(SUM(total_amount), 0),
+                    COALESCE(SUM(total_gas_used), 0),
+                    COALESCE(SUM(match_time_ms), 0),
+                    COALESCE(SUM(receive_time_ms), 0),
+                    COALESCE(SUM(post_time_ms), 0),
+                    COALESCE(SUM(buy_orders), 0),
+                    COALESCE(SUM(sell_orders), 0)
+                FROM transaction_stats
+                WHERE date_trunc($2, created_at) IN (
+                        SELECT DISTINCT date_trunc($2, created_at)
+                        FROM transaction_stats
+                        WHERE recorded_at >= $3
+                    )
+                    AND created_at >= date_trunc('day', now() - make_interval(days => $4))
+                GROUP BY 2, 3
+                ON CONFLICT (bucket, bucket_start, market) DO UPDATE SET
+                    transactions = EXCLUDED.transactions,
+                    matches = EXCLUDED.matches,
+                    volume = EXCLUDED.volume,
+                    gas_used = EXCLUDED.gas_used,
+                    match_time_ms = EXCLUDED.match_time_ms,
+                    receive_time_ms = EXCLUDED.receive_time_ms,
+                    post_time_ms = EXCLUDED.post_time_ms,
+                    buy_orders = EXCLUDED.buy_orders,
+                    sell_orders = EXCLUDED.sell_orders
+                "#,
+                bucket.label(),
+                bucket.unit(),
+                since,
+                self.retention_days,
+            )
+            .execute(&self.db_pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Deletes raw rows from days past the retention period. The cutoff is
+    /// aligned to a day so daily buckets are never rebuilt from partial data.
+    async fn prune(&self) -> Result<u64, sqlx::Error> {
+        let raw = sqlx::query!(
+            r#"
+            DELETE FROM transaction_stats
+            WHERE created_at < date_trunc('day', now() - make_interval(days => $1))
+            "#,
+            self.retention_days,
+        )
+        .execute(&self.db_pool)
+        .await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM transaction_stats_rollup
+            WHERE bucket = '1m' AND bucket_start < date_trunc('day', now() - make_interval(days => $1))
+            "#,
+            self.retention_days,
+        )
+        .execute(&self.db_pool)
+        .await?;
+        Ok(raw.rows_affected())
+    }
+}
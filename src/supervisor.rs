@@ -0,0 +1,188 @@
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::Arc;
+
+use log::{error, info, warn};
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::sync::{Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::{self, Duration, Instant};
+
+use crate::error::Error;
+use crate::util::Shutdown;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// The task is restarted when it returns an error or panics.
+    OnFailure,
+    /// The task is restarted whenever it exits before shutdown.
+    Always,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+pub enum TaskState {
+    Running,
+    Restarting,
+    Stopped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct TaskStatus {
+    pub name: String,
+    pub state: TaskState,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+}
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Owns the long-running tasks of the process and restarts them with
+/// exponential backoff according to their `RestartPolicy`.
+pub struct Supervisor {
+    statuses: Arc<RwLock<BTreeMap<String, TaskStatus>>>,
+    handles: Mutex<HashMap<String, JoinHandle<()>>>,
+    shutdown: Shutdown,
+}
+
+impl Supervisor {
+    pub fn new(shutdown: Shutdown) -> Arc<Self> {
+        Arc::new(Self {
+            statuses: Arc::new(RwLock::new(BTreeMap::new())),
+            handles: Mutex::new(HashMap::new()),
+            shutdown,
+        })
+    }
+
+    /// Runs the future produced by `factory` as task `name`, calling the
+    /// factory again for every restart.
+    pub async fn spawn<F, Fut>(&self, name: &str, policy: RestartPolicy, factory: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        let name = name.to_string();
+        let statuses = self.statuses.clone();
+        let shutdown = self.shutdown.clone();
+
+        statuses.write().await.insert(
+            name.clone(),
+            TaskStatus {
+                name: name.clone(),
+                state: TaskState::Running,
+                restarts: 0,
+                last_error: None,
+            },
+        );
+
+        let task_name = name.clone();
+        let handle = tokio::spawn(async move {
+            let name = task_name;
+            let mut backoff = INITIAL_BACKOFF;
+            loop {
+                let started = Instant::now();
+                let failure = match tokio::spawn(factory()).await {
+                    Ok(Ok(())) => None,
+                    Ok(Err(e)) => Some(e.to_string()),
+                    Err(e) => Some(format!("task panicked: {}", e)),
+                };
+
+                let restart = !shutdown.is_triggered()
+                    && match policy {
+                        RestartPolicy::OnFailure => failure.is_some(),
+                        RestartPolicy::Always => true,
+                    };
+
+                {
+                    let mut statuses = statuses.write().await;
+                    if let Some(status) = statuses.get_mut(&name) {
+                        status.state = match (restart, &failure) {
+                            (true, _) => TaskState::Restarting,
+                            (false, Some(_)) => TaskState::Failed,
+                            (false, None) => TaskState::Stopped,
+                        };
+                        if failure.is_some() {
+                            status.last_error = failure.clone();
+                        }
+                    }
+                }
+
+                if !restart {
+                    match &failure {
+                        Some(e) => error!("Task {} failed: {}", name, e),
+                        None => info!("Task {} stopped", name),
+                    }
+                    return;
+                }
+
+                if started.elapsed() > MAX_BACKOFF {
+                    backoff = INITIAL_BACKOFF;
+                }
+                warn!(
+                    "Task {} exited ({}), restarting in {:?}",
+                    name,
+                    failure.as_deref().unwrap_or("no error"),
+                    backoff
+                );
+                tokio::select! {
+                    _ = time::sleep(backoff) => {}
+                    _ = shutdown.wait() => {
+                        if let Some(status) = statuses.write().await.get_mut(&name) {
+                            status.state = TaskState::Stopped;
+                        }
+                        return;
+                    }
+                }
+                backoff = std::cmp::min(backoff * 2, MAX_BACKOFF);
+
+                if let Some(status) = statuses.write().await.get_mut(&name) {
+                    status.state = TaskState::Running;
+                    status.restarts += 1;
+                }
+            }
+        });
+
+        self.handles.lock().await.insert(name, handle);
+    }
+
+    /// Waits up to `timeout` for task `name` to stop, aborting it afterwards.
+    pub async fn join(&self, name: &str, timeout: Duration) {
+        let handle = self.handles.lock().await.remove(name);
+        if let Some(mut handle) = handle {
+            if time::timeout(timeout, &mut handle).await.is_err() {
+                error!("Task {} did not stop within {:?}, aborting", name, timeout);
+                handle.abort();
+            }
+        }
+    }
+
+    /// Waits for every task whose name starts with `prefix`.
+    pub async fn join_prefix(&self, prefix: &str, timeout: Duration) {
+        let names: Vec<String> = self
+            .handles
+            .lock()
+            .await
+            .keys()
+            .filter(|name| name.starts_with(prefix))
+            .cloned()
+            .collect();
+        for name in names {
+            self.join(&name, timeout).await;
+        }
+    }
+
+    pub async fn statuses(&self) -> Vec<TaskStatus> {
+        self.statuses.read().await.values().cloned().collect()
+    }
+
+    /// Whether a task has failed and will not be restarted.
+    pub async fn any_failed(&self) -> bool {
+        self.statuses
+            .read()
+            .await
+            .values()
+            .any(|status| status.state == TaskState::Failed)
+    }
+}
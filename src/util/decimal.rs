@@ -0,0 +1,19 @@
+use std::str::FromStr;
+
+use sqlx::types::BigDecimal;
+
+/// Exact decimal of a raw `u128`, as stored in `NUMERIC` columns.
+pub fn numeric(value: u128) -> BigDecimal {
+    BigDecimal::from_str(&value.to_string()).expect("integer is a valid decimal")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_every_digit_of_u128() {
+        assert_eq!(numeric(u128::MAX).to_string(), u128::MAX.to_string());
+        assert_eq!(numeric(0), BigDecimal::from(0));
+    }
+}
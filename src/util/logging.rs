@@ -1,9 +1,10 @@
+/*
 use crate::config::ev;
 use chrono::Local;
 use fern::Dispatch;
 use log::LevelFilter;
 use std::fs::OpenOptions;
-/*
+
 pub fn log_level(level_str: &str) -> Result<LevelFilter> {
     match level_str {
         "off" => Ok(LevelFilter::Off),
@@ -1 +1,5 @@
+pub mod decimal;
 pub mod logging;
+pub mod shutdown;
+
+pub use shutdown::Shutdown;
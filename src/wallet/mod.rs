@@ -0,0 +1,8 @@
+pub mod monitor;
+pub mod pool;
+pub mod signer;
+pub mod source;
+
+pub use pool::{WalletLease, WalletPool};
+pub use signer::SignerAccount;
+pub use source::WalletSource;
@@ -0,0 +1,46 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use log::{error, info, warn};
+use tokio::time::{self, Duration};
+
+use super::pool::{FundingStatus, WalletPool};
+
+/// Polls the base asset balance of every pooled wallet and logs funding
+/// status transitions. Wallets below the floor are skipped by `WalletPool::acquire`.
+pub async fn monitor_balances(pool: Arc<WalletPool>, interval: Duration) {
+    let mut last_status: HashMap<usize, FundingStatus> = HashMap::new();
+    let mut ticker = time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+        for wallet in pool.wallets() {
+            let balance = match wallet.refresh_balance().await {
+                Ok(balance) => balance,
+                Err(e) => {
+                    error!(
+                        "Failed to fetch balance of matcher wallet #{}: {:?}",
+                        wallet.index, e
+                    );
+                    continue;
+                }
+            };
+
+            let status = wallet.funding(&pool.thresholds);
+            if last_status.insert(wallet.index, status) == Some(status) {
+                continue;
+            }
+            match status {
+                FundingStatus::Depleted => error!(
+                    "Matcher wallet #{} balance {} is below WALLET_MIN_BALANCE {}, submissions paused",
+                    wallet.index, balance, pool.thresholds.floor
+                ),
+                FundingStatus::Low => warn!(
+                    "Matcher wallet #{} balance {} is below WALLET_WARN_BALANCE {}",
+                    wallet.index, balance, pool.thresholds.warn
+                ),
+                _ => info!("Matcher wallet #{} balance: {}", wallet.index, balance),
+            }
+        }
+    }
+}
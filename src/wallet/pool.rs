@@ -0,0 +1,309 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use fuels::accounts::provider::Provider;
+use fuels::accounts::ViewOnlyAccount;
+use log::info;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use super::{SignerAccount, WalletSource};
+use crate::config::ev_or;
+use crate::error::Error;
+
+#[derive(Default)]
+pub struct WalletStats {
+    pub in_flight: AtomicUsize,
+    pub submitted: AtomicU64,
+    pub failed: AtomicU64,
+    pub balance: AtomicU64,
+    pub balance_checked: AtomicBool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+pub enum FundingStatus {
+    Unknown,
+    Funded,
+    Low,
+    Depleted,
+}
+
+/// Base asset balances below which a wallet is reported as low (`warn`) or
+/// is taken out of rotation (`floor`).
+#[derive(Debug, Clone, Copy)]
+pub struct BalanceThresholds {
+    pub warn: u64,
+    pub floor: u64,
+}
+
+impl BalanceThresholds {
+    pub fn from_env() -> Self {
+        Self {
+            warn: ev_or("WALLET_WARN_BALANCE", 0),
+            floor: ev_or("WALLET_MIN_BALANCE", 0),
+        }
+    }
+}
+
+pub struct PooledWallet {
+    pub index: usize,
+    pub account: SignerAccount,
+    pub stats: WalletStats,
+}
+
+impl PooledWallet {
+    pub async fn refresh_balance(&self) -> Result<u64, Error> {
+        let base_asset_id = *self.account.try_provider()?.base_asset_id();
+        let balance = self.account.get_asset_balance(&base_asset_id).await?;
+        self.stats.balance.store(balance, Ordering::Relaxed);
+        self.stats.balance_checked.store(true, Ordering::Relaxed);
+        Ok(balance)
+    }
+
+    pub fn funding(&self, thresholds: &BalanceThresholds) -> FundingStatus {
+        if !self.stats.balance_checked.load(Ordering::Relaxed) {
+            return FundingStatus::Unknown;
+        }
+        let balance = self.stats.balance.load(Ordering::Relaxed);
+        if balance < thresholds.floor {
+            FundingStatus::Depleted
+        } else if balance < thresholds.warn {
+            FundingStatus::Low
+        } else {
+            FundingStatus::Funded
+        }
+    }
+
+    pub fn status(&self, thresholds: &BalanceThresholds) -> WalletStatus {
+        let submitted = self.stats.submitted.load(Ordering::Relaxed);
+        let failed = self.stats.failed.load(Ordering::Relaxed);
+        WalletStatus {
+            index: self.index,
+            address: self.account.address().to_string(),
+            in_flight: self.stats.in_flight.load(Ordering::Relaxed),
+            submitted,
+            failed,
+            failure_rate: if submitted == 0 {
+                0.0
+            } else {
+                failed as f64 / submitted as f64
+            },
+            balance: self.stats.balance.load(Ordering::Relaxed),
+            funding: self.funding(thresholds),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct WalletStatus {
+    pub index: usize,
+    pub address: String,
+    pub in_flight: usize,
+    pub submitted: u64,
+    pub failed: u64,
+    pub failure_rate: f64,
+    pub balance: u64,
+    pub funding: FundingStatus,
+}
+
+/// Set of matcher wallets that batches are handed out to round-robin, so
+/// several `match_order_many` transactions can be in flight at once.
+pub struct WalletPool {
+    wallets: Vec<Arc<PooledWallet>>,
+    next: AtomicUsize,
+    max_in_flight: usize,
+    pub thresholds: BalanceThresholds,
+}
+
+impl WalletPool {
+    pub fn new(
+        accounts: Vec<SignerAccount>,
+        max_in_flight: usize,
+        thresholds: BalanceThresholds,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            wallets: accounts
+                .into_iter()
+                .enumerate()
+                .map(|(index, account)| {
+                    Arc::new(PooledWallet {
+                        index,
+                        account,
+                        stats: WalletStats::default(),
+                    })
+                })
+                .collect(),
+            next: AtomicUsize::new(0),
+            max_in_flight: max_in_flight.max(1),
+            thresholds,
+        })
+    }
+
+    pub fn from_env(provider: &Provider) -> Result<Arc<Self>, Error> {
+        let accounts = WalletSource::from_env()?
+            .iter()
+            .map(|source| {
+                let wallet = source.load(provider.clone())?;
+                Ok(SignerAccount::new(wallet, Some(provider.clone())))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        if accounts.is_empty() {
+            return Err(Error::WalletError(
+                "No matcher wallets configured".to_string(),
+            ));
+        }
+        for account in &accounts {
+            info!("Matcher wallet: {}", account.address());
+        }
+        Ok(Self::new(
+            accounts,
+            ev_or("MAX_IN_FLIGHT_PER_WALLET", 1usize),
+            BalanceThresholds::from_env(),
+        ))
+    }
+
+    pub fn wallets(&self) -> &[Arc<PooledWallet>] {
+        &self.wallets
+    }
+
+    /// Whether at least one wallet is above the hard balance floor.
+    pub fn can_submit(&self) -> bool {
+        self.wallets
+            .iter()
+            .any(|w| w.funding(&self.thresholds) != FundingStatus::Depleted)
+    }
+
+    /// Picks the next wallet, starting after the previously used one, that
+    /// is above the balance floor and still has room for another in-flight
+    /// transaction.
+    pub fn acquire(&self) -> Option<WalletLease> {
+        let len = self.wallets.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        for offset in 0..len {
+            let wallet = &self.wallets[(start + offset) % len];
+            if wallet.funding(&self.thresholds) == FundingStatus::Depleted {
+                continue;
+            }
+            let claimed = wallet
+                .stats
+                .in_flight
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                    (n < self.max_in_flight).then_some(n + 1)
+                })
+                .is_ok();
+            if claimed {
+                return Some(WalletLease {
+                    wallet: wallet.clone(),
+                });
+            }
+        }
+        None
+    }
+
+    pub fn status(&self) -> Vec<WalletStatus> {
+        self.wallets
+            .iter()
+            .map(|w| w.status(&self.thresholds))
+            .collect()
+    }
+}
+
+/// An in-flight slot on one pooled wallet, released on drop.
+pub struct WalletLease {
+    pub wallet: Arc<PooledWallet>,
+}
+
+impl WalletLease {
+    pub fn record_success(&self) {
+        self.wallet.stats.submitted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_failure(&self) {
+        self.wallet.stats.submitted.fetch_add(1, Ordering::Relaxed);
+        self.wallet.stats.failed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Drop for WalletLease {
+    fn drop(&mut self) {
+        self.wallet.stats.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fuels::accounts::wallet::WalletUnlocked;
+
+    use super::*;
+
+    fn pool(wallets: usize, max_in_flight: usize, floor: u64) -> Arc<WalletPool> {
+        let accounts = (0..wallets)
+            .map(|_| SignerAccount::new(WalletUnlocked::new_random(None), None))
+            .collect();
+        WalletPool::new(
+            accounts,
+            max_in_flight,
+            BalanceThresholds { warn: 0, floor },
+        )
+    }
+
+    fn set_balance(wallet: &PooledWallet, balance: u64) {
+        wallet.stats.balance.store(balance, Ordering::Relaxed);
+        wallet.stats.balance_checked.store(true, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn acquire_rotates_through_the_wallets() {
+        let pool = pool(3, 1, 0);
+        let indexes: Vec<usize> = (0..3)
+            .map(|_| pool.acquire().unwrap())
+            .map(|lease| lease.wallet.index)
+            .collect();
+        assert_eq!(indexes, vec![0, 1, 2]);
+        assert_eq!(pool.acquire().unwrap().wallet.index, 0);
+    }
+
+    #[test]
+    fn acquire_respects_the_in_flight_cap() {
+        let pool = pool(2, 2, 0);
+        let leases: Vec<WalletLease> = (0..4).map(|_| pool.acquire().unwrap()).collect();
+        assert!(pool.acquire().is_none());
+        for wallet in pool.wallets() {
+            assert_eq!(wallet.stats.in_flight.load(Ordering::Relaxed), 2);
+        }
+        drop(leases);
+        assert!(pool.acquire().is_some());
+    }
+
+    #[test]
+    fn acquire_skips_wallets_under_the_floor() {
+        let pool = pool(3, 1, 100);
+        set_balance(&pool.wallets()[0], 50);
+        set_balance(&pool.wallets()[1], 150);
+        // Wallet 2 has not been checked yet and stays in rotation.
+        let first = pool.acquire().unwrap();
+        let second = pool.acquire().unwrap();
+        assert_eq!(first.wallet.index, 1);
+        assert_eq!(second.wallet.index, 2);
+        assert!(pool.acquire().is_none());
+
+        set_balance(&pool.wallets()[2], 0);
+        assert!(pool.can_submit());
+        set_balance(&pool.wallets()[1], 0);
+        assert!(!pool.can_submit());
+    }
+
+    #[test]
+    fn dropping_a_lease_frees_its_slot() {
+        let pool = pool(1, 1, 0);
+        let lease = pool.acquire().unwrap();
+        lease.record_failure();
+        assert!(pool.acquire().is_none());
+        drop(lease);
+
+        let wallet = &pool.wallets()[0];
+        assert_eq!(wallet.stats.in_flight.load(Ordering::Relaxed), 0);
+        assert_eq!(wallet.stats.failed.load(Ordering::Relaxed), 1);
+        assert!(pool.acquire().is_some());
+    }
+}
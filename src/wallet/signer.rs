@@ -0,0 +1,194 @@
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use fuels::accounts::provider::Provider;
+use fuels::accounts::wallet::WalletUnlocked;
+use fuels::accounts::{Account, ViewOnlyAccount};
+use fuels::core::traits::Signer;
+use fuels::crypto::{Message, Signature};
+use fuels::types::bech32::Bech32Address;
+use fuels::types::coin_type_id::CoinTypeId;
+use fuels::types::errors::Error as FuelError;
+use fuels::types::input::Input;
+use fuels::types::transaction_builders::TransactionBuilder;
+use fuels::types::AssetId;
+
+use crate::error::Error;
+
+/// Signing backend for matcher transactions.
+///
+/// Implemented for a local `WalletUnlocked`; a remote signing service can
+/// implement it so the key never has to be present in the matcher process.
+#[async_trait]
+pub trait MatcherSigner: Send + Sync + 'static {
+    fn address(&self) -> &Bech32Address;
+
+    async fn sign_message(&self, message: Message) -> Result<Signature, Error>;
+}
+
+#[async_trait]
+impl MatcherSigner for WalletUnlocked {
+    fn address(&self) -> &Bech32Address {
+        ViewOnlyAccount::address(self)
+    }
+
+    async fn sign_message(&self, message: Message) -> Result<Signature, Error> {
+        Ok(Signer::sign(self, message).await?)
+    }
+}
+
+/// Adapts a `MatcherSigner` to the fuels `Signer` trait so it can be
+/// attached to transaction builders.
+#[derive(Clone)]
+struct SignerAdapter(Arc<dyn MatcherSigner>);
+
+#[async_trait]
+impl Signer for SignerAdapter {
+    async fn sign(&self, message: Message) -> fuels::types::errors::Result<Signature> {
+        self.0
+            .sign_message(message)
+            .await
+            .map_err(|e| FuelError::Other(e.to_string()))
+    }
+
+    fn address(&self) -> &Bech32Address {
+        self.0.address()
+    }
+}
+
+/// Account that spends the signer's coins and has the signer witness every
+/// transaction, so contract calls go through `MatcherSigner` instead of a
+/// local wallet.
+#[derive(Clone)]
+pub struct SignerAccount {
+    signer: Arc<dyn MatcherSigner>,
+    provider: Option<Provider>,
+}
+
+impl SignerAccount {
+    pub fn new(signer: impl MatcherSigner, provider: Option<Provider>) -> Self {
+        Self {
+            signer: Arc::new(signer),
+            provider,
+        }
+    }
+}
+
+impl fmt::Debug for SignerAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignerAccount")
+            .field("address", self.signer.address())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl ViewOnlyAccount for SignerAccount {
+    fn address(&self) -> &Bech32Address {
+        self.signer.address()
+    }
+
+    fn try_provider(&self) -> fuels::types::errors::Result<&Provider> {
+        self.provider
+            .as_ref()
+            .ok_or_else(|| FuelError::Other("No provider set for matcher account".to_string()))
+    }
+}
+
+#[async_trait]
+impl Account for SignerAccount {
+    async fn get_asset_inputs_for_amount(
+        &self,
+        asset_id: AssetId,
+        amount: u64,
+        excluded_coins: Option<Vec<CoinTypeId>>,
+    ) -> fuels::types::errors::Result<Vec<Input>> {
+        Ok(self
+            .get_spendable_resources(asset_id, amount, excluded_coins)
+            .await?
+            .into_iter()
+            .map(Input::resource_signed)
+            .collect())
+    }
+
+    fn add_witnesses<Tb: TransactionBuilder>(
+        &self,
+        tb: &mut Tb,
+    ) -> fuels::types::errors::Result<()> {
+        tb.add_signer(SignerAdapter(self.signer.clone()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fuels::crypto::SecretKey;
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Stands in for a remote signing service: answers with a fixed
+    /// signature and counts the requests.
+    struct StubSigner {
+        address: Bech32Address,
+        signature: Signature,
+        requests: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl MatcherSigner for StubSigner {
+        fn address(&self) -> &Bech32Address {
+            &self.address
+        }
+
+        async fn sign_message(&self, _message: Message) -> Result<Signature, Error> {
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            Ok(self.signature)
+        }
+    }
+
+    fn stub() -> (StubSigner, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let signer = StubSigner {
+            address: Bech32Address::default(),
+            signature: Signature::from_bytes([7; 64]),
+            requests: requests.clone(),
+        };
+        (signer, requests)
+    }
+
+    #[test]
+    fn account_takes_the_signer_address() {
+        let (signer, _) = stub();
+        let address = signer.address.clone();
+        let account = SignerAccount::new(signer, None);
+        assert_eq!(ViewOnlyAccount::address(&account), &address);
+        assert!(account.try_provider().is_err());
+    }
+
+    #[tokio::test]
+    async fn transactions_are_signed_by_the_matcher_signer() {
+        let (signer, requests) = stub();
+        let account = SignerAccount::new(signer, None);
+        let adapter = SignerAdapter(account.signer.clone());
+
+        let signature = adapter.sign(Message::new([1u8; 32])).await.unwrap();
+
+        assert_eq!(signature, Signature::from_bytes([7; 64]));
+        assert_eq!(requests.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn local_wallet_signs_with_its_key() {
+        let secret_key =
+            SecretKey::from_str("5f70feeff1f229e4a95e1056e8b4d80d0b24b565674860cc213bdb07127ce1b1")
+                .unwrap();
+        let wallet = WalletUnlocked::new_from_private_key(secret_key, None);
+        let message = Message::new([1u8; 32]);
+
+        let signature = wallet.sign_message(message).await.unwrap();
+
+        assert!(signature.verify(&secret_key.public_key(), &message).is_ok());
+    }
+}
@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use fuels::accounts::{provider::Provider, wallet::WalletUnlocked};
+use fuels::crypto::SecretKey;
+
+use crate::config::{ev, ev_or};
+use crate::error::Error;
+
+/// Where the matcher wallet key material comes from, selected with `WALLET_SOURCE`.
+#[derive(Debug, Clone)]
+pub enum WalletSource {
+    Mnemonic {
+        phrase: String,
+        derivation_path: Option<String>,
+    },
+    PrivateKey(String),
+    Keystore {
+        path: PathBuf,
+        password_file: PathBuf,
+    },
+}
+
+impl WalletSource {
+    /// Reads every configured matcher wallet. `MATCHER_WALLET_COUNT` derives
+    /// that many accounts from the mnemonic; `PRIVATE_KEY` and `KEYSTORE_PATH`
+    /// accept comma-separated lists.
+    pub fn from_env() -> Result<Vec<Self>, Error> {
+        let source = ev("WALLET_SOURCE").unwrap_or_else(|_| "mnemonic".to_string());
+        match source.to_lowercase().as_str() {
+            "mnemonic" => {
+                let phrase = ev("MNEMONIC")?;
+                let count = ev_or("MATCHER_WALLET_COUNT", 1usize);
+                match ev("DERIVATION_PATH").ok() {
+                    Some(path) if count <= 1 => Ok(vec![WalletSource::Mnemonic {
+                        phrase,
+                        derivation_path: Some(path),
+                    }]),
+                    Some(_) => Err(Error::WalletError(
+                        "DERIVATION_PATH cannot be combined with MATCHER_WALLET_COUNT > 1"
+                            .to_string(),
+                    )),
+                    None => Ok((0..count.max(1))
+                        .map(|i| WalletSource::Mnemonic {
+                            phrase: phrase.clone(),
+                            derivation_path: Some(format!("m/44'/1179993420'/{}'/0/0", i)),
+                        })
+                        .collect()),
+                }
+            }
+            "private_key" => Ok(split_list(&ev("PRIVATE_KEY")?)
+                .map(|key| WalletSource::PrivateKey(key.to_string()))
+                .collect()),
+            "keystore" => {
+                let password_file = PathBuf::from(ev("KEYSTORE_PASSWORD_FILE")?);
+                Ok(split_list(&ev("KEYSTORE_PATH")?)
+                    .map(|path| WalletSource::Keystore {
+                        path: PathBuf::from(path),
+                        password_file: password_file.clone(),
+                    })
+                    .collect())
+            }
+            other => Err(Error::WalletError(format!(
+                "Unknown WALLET_SOURCE `{}`, expected mnemonic, private_key or keystore",
+                other
+            ))),
+        }
+    }
+
+    pub fn load(&self, provider: Provider) -> Result<WalletUnlocked, Error> {
+        match self {
+            WalletSource::Mnemonic {
+                phrase,
+                derivation_path: Some(path),
+            } => Ok(WalletUnlocked::new_from_mnemonic_phrase_with_path(
+                phrase,
+                Some(provider),
+                path,
+            )?),
+            WalletSource::Mnemonic { phrase, .. } => Ok(WalletUnlocked::new_from_mnemonic_phrase(
+                phrase,
+                Some(provider),
+            )?),
+            WalletSource::PrivateKey(key) => {
+                let secret_key = SecretKey::from_str(key.trim_start_matches("0x"))
+                    .map_err(|_| Error::FuelCryptoPrivParseError)?;
+                Ok(WalletUnlocked::new_from_private_key(
+                    secret_key,
+                    Some(provider),
+                ))
+            }
+            WalletSource::Keystore {
+                path,
+                password_file,
+            } => {
+                let password = fs::read_to_string(password_file)?;
+                Ok(WalletUnlocked::load_keystore(
+                    path,
+                    password.trim_end_matches(['\r', '\n']),
+                    Some(provider),
+                )?)
+            }
+        }
+    }
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|v| !v.is_empty())
+}
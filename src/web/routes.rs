@@ -1,21 +1,43 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
+use rocket::http::{ContentType, Status};
+use rocket::response::stream::{Event, EventStream};
+use rocket::response::Debug;
 use rocket::serde::json::Json;
-use rocket::{self, get, Route, State};
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{self, get, routes, Route, Shutdown, State};
 use rocket_okapi::settings::UrlObject;
 use rocket_okapi::swagger_ui::SwaggerUIConfig;
 use rocket_okapi::{openapi, openapi_get_routes, JsonSchema};
 use serde::Serialize;
-use sqlx::types::BigDecimal;
 use sqlx::PgPool;
-use tokio::sync::RwLock;
+use tokio::time::Duration;
 
-use crate::management::manager::OrderManager;
-use crate::model::SpotOrder;
+use crate::config::ev_or;
+use crate::events::EventBus;
+use crate::health::{HealthState, TransactionOutcomeReport};
+use crate::leader::LeaderElection;
+use crate::market::assets::{AssetsInfo, MarketAssets};
+use crate::market::MarketRegistry;
+use crate::metrics;
+use crate::model::{PriceLevel, SpotOrder, TopOfBook};
+use crate::stats::{self, StatsBucket};
+use crate::supervisor::{Supervisor, TaskStatus};
+use crate::wallet::pool::WalletStatus;
+use crate::wallet::WalletPool;
 
 #[derive(Serialize, JsonSchema)]
 pub struct StatsResponse {
     pub total_transactions: i64,
+    pub total_matches: i64,
+    /// Exact sum of matched base amounts.
+    pub total_amount: String,
+    /// `total_amount` in base asset units; only with `market`, since raw
+    /// amounts of different markets do not add up.
+    pub total_amount_decimal: Option<String>,
+    /// Gas per match.
     pub avg_gas_used: String,
     pub total_gas_used: i64,
     pub avg_match_time_ms: String,
@@ -23,89 +45,835 @@ pub struct StatsResponse {
     pub sell_orders: i64,
     pub avg_receive_time_ms: String,
     pub avg_post_time_ms: String,
+    pub bucket: Option<StatsBucket>,
+    /// Empty unless `bucket` is requested.
+    pub series: Vec<StatsPoint>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct StatsPoint {
+    /// RFC 3339 start of the bucket.
+    pub bucket_start: String,
+    pub transactions: i64,
+    pub matches: i64,
+    pub volume: String,
+    pub volume_decimal: Option<String>,
+    pub gas_used: i64,
+    pub avg_match_time_ms: String,
+    pub avg_receive_time_ms: String,
+    pub avg_post_time_ms: String,
+}
+
+/// An order with its raw amount and price, plus both in asset units.
+#[derive(Serialize, JsonSchema)]
+pub struct OrderView {
+    #[serde(flatten)]
+    pub order: SpotOrder,
+    pub amount_decimal: String,
+    pub price_decimal: String,
 }
 
 #[derive(Serialize, JsonSchema)]
 pub struct OrdersResponse {
-    pub orders: Vec<SpotOrder>,
+    pub orders: Vec<OrderView>,
 }
 
 #[derive(Serialize, JsonSchema)]
 pub struct CurrentOrdersResponse {
-    pub buy_orders: Vec<SpotOrder>,
-    pub sell_orders: Vec<SpotOrder>,
+    pub buy_orders: Vec<OrderView>,
+    pub sell_orders: Vec<OrderView>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct PriceLevelView {
+    #[serde(flatten)]
+    pub level: PriceLevel,
+    pub price_decimal: String,
+    pub total_amount_decimal: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct BookDepthResponse {
+    /// Best (highest) bid first.
+    pub bids: Vec<PriceLevelView>,
+    /// Best (lowest) ask first.
+    pub asks: Vec<PriceLevelView>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TopOfBookResponse {
+    #[serde(flatten)]
+    pub top: TopOfBook,
+    pub best_bid_decimal: Option<String>,
+    pub best_ask_decimal: Option<String>,
+    pub spread_decimal: Option<String>,
+    pub mid_decimal: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct WalletsResponse {
+    pub wallets: Vec<WalletStatus>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct LivenessResponse {
+    pub alive: bool,
+    pub tasks: Vec<TaskStatus>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    /// Human-readable reasons why the service is not ready.
+    pub reasons: Vec<String>,
+    pub indexer_connected: bool,
+    pub secs_since_data_frame: Option<f64>,
+    /// Seconds since the last matching cycle, per market.
+    pub matcher_cycle_age_secs: HashMap<String, f64>,
+    pub last_transaction: Option<TransactionOutcomeReport>,
+    pub database_connected: bool,
+    pub can_submit: bool,
+    /// Whether this replica holds the leader lock (always true without leader election).
+    pub leader: bool,
+    pub wallets: Vec<WalletStatus>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct MarketsResponse {
+    pub markets: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct MarketInfoResponse {
+    pub id: String,
+    #[serde(flatten)]
+    pub assets: AssetsInfo,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct MatchTransaction {
+    pub tx_id: String,
+    pub market: String,
+    /// `confirmed`, `reverted` or `squeezed_out`.
+    pub status: String,
+    pub wallet: String,
+    pub gas_used: Option<i64>,
+    pub block_height: Option<i64>,
+    /// RFC 3339 timestamps.
+    pub submitted_at: String,
+    pub completed_at: Option<String>,
 }
 
+#[derive(Serialize, JsonSchema)]
+pub struct MatchTransactionsResponse {
+    pub transactions: Vec<MatchTransaction>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct MatchedTradeRecord {
+    pub tx_id: String,
+    pub market: String,
+    pub buy_order_id: String,
+    pub sell_order_id: String,
+    pub buy_user: String,
+    pub sell_user: String,
+    pub price: String,
+    pub amount: String,
+    /// `None` for markets that are no longer configured.
+    pub price_decimal: Option<String>,
+    pub amount_decimal: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct FailedTransaction {
+    pub market: String,
+    pub wallet: String,
+    pub order_ids: Vec<String>,
+    pub reason: String,
+    pub revert_code: Option<String>,
+    pub error: String,
+    pub submitted_at: String,
+    pub failed_at: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct FailedTransactionsResponse {
+    pub transactions: Vec<FailedTransaction>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct FailureCount {
+    pub reason: String,
+    pub revert_code: Option<String>,
+    pub count: i64,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct FailureStatsResponse {
+    pub confirmed: i64,
+    pub failed: i64,
+    /// Failed share of all submissions, 0 when there were none.
+    pub failure_rate: f64,
+    /// Most frequent first.
+    pub by_reason: Vec<FailureCount>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct MatchedTradesResponse {
+    pub trades: Vec<MatchedTradeRecord>,
+}
+
+/// Totals in the `from`..`to` window (unix seconds), plus a time series from
+/// the rollup tables when `bucket` is given. Raw rows only cover the retention
+/// period; older days are taken from the daily rollup, so the window is
+/// widened to whole days there.
 #[openapi]
-#[get("/stats")]
-async fn get_stats(db: &State<PgPool>) -> Json<StatsResponse> {
+#[get("/stats?<market>&<from>&<to>&<bucket>")]
+async fn get_stats(
+    db: &State<PgPool>,
+    registry: &State<Arc<MarketRegistry>>,
+    market: Option<String>,
+    from: Option<i64>,
+    to: Option<i64>,
+    bucket: Option<StatsBucket>,
+) -> Result<Json<StatsResponse>, Debug<sqlx::Error>> {
     let row = sqlx::query!(
         r#"
+        WITH cutoff AS (
+            SELECT date_trunc('day', now() - make_interval(days => $4)) AS at
+        )
         SELECT
-            COUNT(*) AS total_transactions,
-            COALESCE(AVG(avg_gas_used), 0) AS avg_gas_used,
-            COALESCE(SUM(total_gas_used), 0) AS total_gas_used,
-            COALESCE(AVG(match_time_ms), 0) AS avg_match_time_ms,
-            COALESCE(SUM(buy_orders), 0) AS buy_orders,
-            COALESCE(SUM(sell_orders), 0) AS sell_orders,
-            COALESCE(AVG(receive_time_ms), 0) AS avg_receive_time_ms,
-            COALESCE(AVG(post_time_ms), 0) AS avg_post_time_ms
-        FROM transaction_stats
+            COALESCE(SUM(transactions), 0)::BIGINT AS "total_transactions!",
+            COALESCE(SUM(matches), 0)::BIGINT AS "total_matches!",
+            COALESCE(SUM(volume), 0) AS "total_amount!",
+            COALESCE(SUM(gas_used)::NUMERIC / NULLIF(SUM(matches), 0), 0) AS "avg_gas_used!",
+            COALESCE(SUM(gas_used), 0)::BIGINT AS "total_gas_used!",
+            COALESCE(SUM(match_time_ms)::NUMERIC / NULLIF(SUM(transactions), 0), 0) AS "avg_match_time_ms!",
+            COALESCE(SUM(buy_orders), 0)::BIGINT AS "buy_orders!",
+            COALESCE(SUM(sell_orders), 0)::BIGINT AS "sell_orders!",
+            COALESCE(SUM(receive_time_ms)::NUMERIC / NULLIF(SUM(transactions), 0), 0) AS "avg_receive_time_ms!",
+            COALESCE(SUM(post_time_ms)::NUMERIC / NULLIF(SUM(transactions), 0), 0) AS "avg_post_time_ms!"
+        FROM (
+            SELECT transactions, matches, volume, gas_used, match_time_ms, buy_orders, sell_orders, receive_time_ms, post_time_ms
+            FROM transaction_stats_rollup, cutoff
+            WHERE bucket = '1d'
+              AND bucket_start < cutoff.at
+              AND ($1::TEXT IS NULL OR market = $1)
+              AND ($2::BIGINT IS NULL OR bucket_start >= date_trunc('day', to_timestamp($2)))
+              AND ($3::BIGINT IS NULL OR bucket_start < to_timestamp($3))
+            UNION ALL
+            SELECT 1, total_transactions, total_amount, total_gas_used, match_time_ms, buy_orders, sell_orders, receive_time_ms, post_time_ms
+            FROM transaction_stats, cutoff
+            WHERE created_at >= cutoff.at
+              AND ($1::TEXT IS NULL OR market = $1)
+              AND ($2::BIGINT IS NULL OR created_at >= to_timestamp($2))
+              AND ($3::BIGINT IS NULL OR created_at < to_timestamp($3))
+        ) AS totals
+        "#,
+        market,
+        from,
+        to,
+        stats::retention_days(),
+    )
+    .fetch_one(&**db)
+    .await?;
+
+    let assets = market
+        .as_deref()
+        .and_then(|market| registry.get(market))
+        .map(|market| &market.assets);
+    let series = match bucket {
+        Some(bucket) => stats_series(db, bucket, market.as_deref(), assets, from, to).await?,
+        None => Vec::new(),
+    };
+
+    Ok(Json(StatsResponse {
+        total_transactions: row.total_transactions,
+        total_matches: row.total_matches,
+        total_amount_decimal: assets.map(|a| a.amount_numeric(&row.total_amount).to_string()),
+        total_amount: row.total_amount.to_string(),
+        avg_gas_used: row.avg_gas_used.with_scale(2).to_string(),
+        total_gas_used: row.total_gas_used,
+        avg_match_time_ms: row.avg_match_time_ms.with_scale(2).to_string(),
+        buy_orders: row.buy_orders,
+        sell_orders: row.sell_orders,
+        avg_receive_time_ms: row.avg_receive_time_ms.with_scale(2).to_string(),
+        avg_post_time_ms: row.avg_post_time_ms.with_scale(2).to_string(),
+        bucket,
+        series,
+    }))
+}
+
+async fn stats_series(
+    db: &PgPool,
+    bucket: StatsBucket,
+    market: Option<&str>,
+    assets: Option<&MarketAssets>,
+    from: Option<i64>,
+    to: Option<i64>,
+) -> Result<Vec<StatsPoint>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            bucket_start,
+            SUM(transactions)::BIGINT AS "transactions!",
+            SUM(matches)::BIGINT AS "matches!",
+            SUM(volume) AS "volume!",
+            SUM(gas_used)::BIGINT AS "gas_used!",
+            SUM(match_time_ms)::NUMERIC / SUM(transactions) AS "avg_match_time_ms!",
+            SUM(receive_time_ms)::NUMERIC / SUM(transactions) AS "avg_receive_time_ms!",
+            SUM(post_time_ms)::NUMERIC / SUM(transactions) AS "avg_post_time_ms!"
+        FROM transaction_stats_rollup
+        WHERE bucket = $1
+          AND ($2::TEXT IS NULL OR market = $2)
+          AND ($3::BIGINT IS NULL OR bucket_start >= date_trunc($4, to_timestamp($3)))
+          AND ($5::BIGINT IS NULL OR bucket_start < to_timestamp($5))
+        GROUP BY bucket_start
+        ORDER BY bucket_start
+        "#,
+        bucket.label(),
+        market,
+        from,
+        bucket.unit(),
+        to,
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| StatsPoint {
+            bucket_start: row.bucket_start.to_rfc3339(),
+            transactions: row.transactions,
+            matches: row.matches,
+            volume_decimal: assets.map(|a| a.amount_numeric(&row.volume).to_string()),
+            volume: row.volume.to_string(),
+            gas_used: row.gas_used,
+            avg_match_time_ms: row.avg_match_time_ms.with_scale(2).to_string(),
+            avg_receive_time_ms: row.avg_receive_time_ms.with_scale(2).to_string(),
+            avg_post_time_ms: row.avg_post_time_ms.with_scale(2).to_string(),
+        })
+        .collect())
+}
+
+/// Match transactions, newest first. `from` and `to` are unix timestamps in
+/// seconds.
+#[openapi]
+#[get("/history/transactions?<tx_id>&<wallet>&<market>&<from>&<to>&<limit>")]
+async fn get_transaction_history(
+    db: &State<PgPool>,
+    tx_id: Option<String>,
+    wallet: Option<String>,
+    market: Option<String>,
+    from: Option<i64>,
+    to: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Json<MatchTransactionsResponse>, Debug<sqlx::Error>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT tx_id, market, status, wallet, gas_used, block_height, submitted_at, completed_at
+        FROM match_transactions
+        WHERE ($1::TEXT IS NULL OR tx_id = $1)
+          AND ($2::TEXT IS NULL OR wallet = $2)
+          AND ($3::TEXT IS NULL OR market = $3)
+          AND ($4::BIGINT IS NULL OR submitted_at >= to_timestamp($4))
+          AND ($5::BIGINT IS NULL OR submitted_at < to_timestamp($5))
+        ORDER BY submitted_at DESC
+        LIMIT $6
+        "#,
+        tx_id,
+        wallet,
+        market,
+        from,
+        to,
+        history_limit(limit),
+    )
+    .fetch_all(&**db)
+    .await?;
+
+    Ok(Json(MatchTransactionsResponse {
+        transactions: rows
+            .into_iter()
+            .map(|row| MatchTransaction {
+                tx_id: row.tx_id,
+                market: row.market,
+                status: row.status,
+                wallet: row.wallet,
+                gas_used: row.gas_used,
+                block_height: row.block_height,
+                submitted_at: row.submitted_at.to_rfc3339(),
+                completed_at: row.completed_at.map(|at| at.to_rfc3339()),
+            })
+            .collect(),
+    }))
+}
+
+/// Individual fills, newest first. `order_id` and `user` match either side.
+#[openapi]
+#[get("/history/trades?<order_id>&<user>&<tx_id>&<market>&<from>&<to>&<limit>")]
+#[allow(clippy::too_many_arguments)]
+async fn get_trade_history(
+    db: &State<PgPool>,
+    registry: &State<Arc<MarketRegistry>>,
+    order_id: Option<String>,
+    user: Option<String>,
+    tx_id: Option<String>,
+    market: Option<String>,
+    from: Option<i64>,
+    to: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Json<MatchedTradesResponse>, Debug<sqlx::Error>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT tx_id, market, buy_order_id, sell_order_id, buy_user, sell_user, price, amount, created_at
+        FROM matched_trades
+        WHERE ($1::TEXT IS NULL OR buy_order_id = $1 OR sell_order_id = $1)
+          AND ($2::TEXT IS NULL OR buy_user = $2 OR sell_user = $2)
+          AND ($3::TEXT IS NULL OR tx_id = $3)
+          AND ($4::TEXT IS NULL OR market = $4)
+          AND ($5::BIGINT IS NULL OR created_at >= to_timestamp($5))
+          AND ($6::BIGINT IS NULL OR created_at < to_timestamp($6))
+        ORDER BY created_at DESC, id DESC
+        LIMIT $7
+        "#,
+        order_id,
+        user,
+        tx_id,
+        market,
+        from,
+        to,
+        history_limit(limit),
+    )
+    .fetch_all(&**db)
+    .await?;
+
+    Ok(Json(MatchedTradesResponse {
+        trades: rows
+            .into_iter()
+            .map(|row| {
+                let assets = registry.get(&row.market).map(|market| &market.assets);
+                MatchedTradeRecord {
+                    price_decimal: assets.map(|a| a.price_numeric(&row.price).to_string()),
+                    amount_decimal: assets.map(|a| a.amount_numeric(&row.amount).to_string()),
+                    tx_id: row.tx_id,
+                    market: row.market,
+                    buy_order_id: row.buy_order_id,
+                    sell_order_id: row.sell_order_id,
+                    buy_user: row.buy_user,
+                    sell_user: row.sell_user,
+                    price: row.price.to_string(),
+                    amount: row.amount.to_string(),
+                    created_at: row.created_at.to_rfc3339(),
+                }
+            })
+            .collect(),
+    }))
+}
+
+/// Failed submissions, newest first. `from` and `to` are unix timestamps in
+/// seconds.
+#[openapi]
+#[get("/transactions/failed?<market>&<reason>&<from>&<to>&<limit>")]
+async fn get_failed_transactions(
+    db: &State<PgPool>,
+    market: Option<String>,
+    reason: Option<String>,
+    from: Option<i64>,
+    to: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Json<FailedTransactionsResponse>, Debug<sqlx::Error>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT market, wallet, order_ids, reason, revert_code, error, submitted_at, failed_at
+        FROM failed_transactions
+        WHERE ($1::TEXT IS NULL OR market = $1)
+          AND ($2::TEXT IS NULL OR reason = $2)
+          AND ($3::BIGINT IS NULL OR failed_at >= to_timestamp($3))
+          AND ($4::BIGINT IS NULL OR failed_at < to_timestamp($4))
+        ORDER BY failed_at DESC
+        LIMIT $5
+        "#,
+        market,
+        reason,
+        from,
+        to,
+        history_limit(limit),
+    )
+    .fetch_all(&**db)
+    .await?;
+
+    Ok(Json(FailedTransactionsResponse {
+        transactions: rows
+            .into_iter()
+            .map(|row| FailedTransaction {
+                market: row.market,
+                wallet: row.wallet,
+                order_ids: row.order_ids,
+                reason: row.reason,
+                revert_code: row.revert_code,
+                error: row.error,
+                submitted_at: row.submitted_at.to_rfc3339(),
+                failed_at: row.failed_at.to_rfc3339(),
+            })
+            .collect(),
+    }))
+}
+
+/// Failure rate and failure counts grouped by reason and revert code.
+#[openapi]
+#[get("/transactions/failed/stats?<market>&<from>&<to>")]
+async fn get_failure_stats(
+    db: &State<PgPool>,
+    market: Option<String>,
+    from: Option<i64>,
+    to: Option<i64>,
+) -> Result<Json<FailureStatsResponse>, Debug<sqlx::Error>> {
+    let confirmed = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM match_transactions
+        WHERE status = 'confirmed'
+          AND ($1::TEXT IS NULL OR market = $1)
+          AND ($2::BIGINT IS NULL OR submitted_at >= to_timestamp($2))
+          AND ($3::BIGINT IS NULL OR submitted_at < to_timestamp($3))
         "#,
+        market,
+        from,
+        to,
     )
     .fetch_one(&**db)
-    .await
-    .unwrap();
-
-    Json(StatsResponse {
-        total_transactions: row.total_transactions.unwrap_or(0),
-        avg_gas_used: row.avg_gas_used.unwrap_or(BigDecimal::from(0)).to_string(),
-        total_gas_used: row.total_gas_used.unwrap_or(0),
-        avg_match_time_ms: row
-            .avg_match_time_ms
-            .unwrap_or(BigDecimal::from(0))
-            .to_string(),
-        buy_orders: row.buy_orders.unwrap_or(0),
-        sell_orders: row.sell_orders.unwrap_or(0),
-        avg_receive_time_ms: row
-            .avg_receive_time_ms
-            .unwrap_or(BigDecimal::from(0))
-            .to_string(),
-        avg_post_time_ms: row
-            .avg_post_time_ms
-            .unwrap_or(BigDecimal::from(0))
-            .to_string(),
+    .await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT reason, revert_code, COUNT(*) AS "count!"
+        FROM failed_transactions
+        WHERE ($1::TEXT IS NULL OR market = $1)
+          AND ($2::BIGINT IS NULL OR failed_at >= to_timestamp($2))
+          AND ($3::BIGINT IS NULL OR failed_at < to_timestamp($3))
+        GROUP BY reason, revert_code
+        ORDER BY 3 DESC
+        "#,
+        market,
+        from,
+        to,
+    )
+    .fetch_all(&**db)
+    .await?;
+
+    let failed: i64 = rows.iter().map(|row| row.count).sum();
+    let total = confirmed + failed;
+    Ok(Json(FailureStatsResponse {
+        confirmed,
+        failed,
+        failure_rate: if total == 0 {
+            0.0
+        } else {
+            failed as f64 / total as f64
+        },
+        by_reason: rows
+            .into_iter()
+            .map(|row| FailureCount {
+                reason: row.reason,
+                revert_code: row.revert_code,
+                count: row.count,
+            })
+            .collect(),
+    }))
+}
+
+fn history_limit(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(100).clamp(1, 1000)
+}
+
+#[openapi]
+#[get("/markets")]
+async fn get_markets(registry: &State<Arc<MarketRegistry>>) -> Json<MarketsResponse> {
+    Json(MarketsResponse {
+        markets: registry.markets().iter().map(|m| m.id.clone()).collect(),
     })
 }
 
+/// Base and quote assets of a market and the decimals of its raw amounts
+/// and prices.
+#[openapi]
+#[get("/markets/<market>/info")]
+async fn get_market_info(
+    registry: &State<Arc<MarketRegistry>>,
+    market: &str,
+) -> Option<Json<MarketInfoResponse>> {
+    let market = registry.get(market)?;
+    Some(Json(MarketInfoResponse {
+        id: market.id.clone(),
+        assets: market.assets.info(),
+    }))
+}
+
 #[openapi]
-#[get("/orders/buy")]
-async fn get_buy_orders(manager: &State<Arc<OrderManager>>) -> Json<OrdersResponse> {
-    let buy_orders = manager.get_all_buy_orders().await;
-    Json(OrdersResponse { orders: buy_orders })
+#[get("/markets/<market>/orders/buy")]
+async fn get_buy_orders(
+    registry: &State<Arc<MarketRegistry>>,
+    market: &str,
+) -> Option<Json<OrdersResponse>> {
+    let market = registry.get(market)?;
+    let buy_orders = market.order_manager.get_all_buy_orders().await;
+    Some(Json(OrdersResponse {
+        orders: order_views(&market.assets, buy_orders),
+    }))
 }
 
 #[openapi]
-#[get("/orders/sell")]
-async fn get_sell_orders(manager: &State<Arc<OrderManager>>) -> Json<OrdersResponse> {
-    let sell_orders = manager.get_all_sell_orders().await;
-    Json(OrdersResponse {
-        orders: sell_orders,
+#[get("/markets/<market>/orders/sell")]
+async fn get_sell_orders(
+    registry: &State<Arc<MarketRegistry>>,
+    market: &str,
+) -> Option<Json<OrdersResponse>> {
+    let market = registry.get(market)?;
+    let sell_orders = market.order_manager.get_all_sell_orders().await;
+    Some(Json(OrdersResponse {
+        orders: order_views(&market.assets, sell_orders),
+    }))
+}
+
+#[openapi]
+#[get("/markets/<market>/orders/all")]
+async fn get_all_orders(
+    registry: &State<Arc<MarketRegistry>>,
+    market: &str,
+) -> Option<Json<CurrentOrdersResponse>> {
+    let market = registry.get(market)?;
+    let (buy_orders, sell_orders) = market.order_manager.get_all_orders().await;
+    Some(Json(CurrentOrdersResponse {
+        buy_orders: order_views(&market.assets, buy_orders),
+        sell_orders: order_views(&market.assets, sell_orders),
+    }))
+}
+
+fn order_views(assets: &MarketAssets, orders: Vec<SpotOrder>) -> Vec<OrderView> {
+    orders
+        .into_iter()
+        .map(|order| OrderView {
+            amount_decimal: assets.amount(order.amount).to_string(),
+            price_decimal: assets.price(order.price).to_string(),
+            order,
+        })
+        .collect()
+}
+
+#[openapi]
+#[get("/markets/<market>/book/depth?<levels>")]
+async fn get_book_depth(
+    registry: &State<Arc<MarketRegistry>>,
+    market: &str,
+    levels: Option<usize>,
+) -> Option<Json<BookDepthResponse>> {
+    let market = registry.get(market)?;
+    let levels = levels.unwrap_or(10).clamp(1, 500);
+    let depth = market.order_manager.depth(levels).await;
+    let view = |level: PriceLevel| PriceLevelView {
+        price_decimal: market.assets.price(level.price).to_string(),
+        total_amount_decimal: market.assets.amount(level.total_amount).to_string(),
+        level,
+    };
+    Some(Json(BookDepthResponse {
+        bids: depth.bids.into_iter().map(view).collect(),
+        asks: depth.asks.into_iter().map(view).collect(),
+    }))
+}
+
+#[openapi]
+#[get("/markets/<market>/book/top")]
+async fn get_book_top(
+    registry: &State<Arc<MarketRegistry>>,
+    market: &str,
+) -> Option<Json<TopOfBookResponse>> {
+    let market = registry.get(market)?;
+    let top = market.order_manager.top_of_book().await;
+    let price = |raw: Option<u128>| raw.map(|raw| market.assets.price(raw));
+    let (best_bid, best_ask) = (price(top.best_bid), price(top.best_ask));
+    Some(Json(TopOfBookResponse {
+        spread_decimal: best_bid
+            .as_ref()
+            .zip(best_ask.as_ref())
+            .map(|(bid, ask)| (ask - bid).to_string()),
+        mid_decimal: price(top.mid).map(|mid| mid.to_string()),
+        best_bid_decimal: best_bid.map(|bid| bid.to_string()),
+        best_ask_decimal: best_ask.map(|ask| ask.to_string()),
+        top,
+    }))
+}
+
+#[openapi]
+#[get("/wallets")]
+async fn get_wallets(pool: &State<Arc<WalletPool>>) -> Json<WalletsResponse> {
+    Json(WalletsResponse {
+        wallets: pool.status(),
     })
 }
 
 #[openapi]
-#[get("/orders/all")]
-async fn get_all_orders(manager: &State<Arc<OrderManager>>) -> Json<CurrentOrdersResponse> {
-    let (buy_orders, sell_orders) = manager.get_all_orders().await;
-    Json(CurrentOrdersResponse {
-        buy_orders,
-        sell_orders,
+#[get("/health/live")]
+async fn get_health_live(supervisor: &State<Arc<Supervisor>>) -> (Status, Json<LivenessResponse>) {
+    let alive = !supervisor.any_failed().await;
+    let status = if alive {
+        Status::Ok
+    } else {
+        Status::ServiceUnavailable
+    };
+    (
+        status,
+        Json(LivenessResponse {
+            alive,
+            tasks: supervisor.statuses().await,
+        }),
+    )
+}
+
+#[openapi]
+#[get("/health/ready")]
+async fn get_health_ready(
+    db: &State<PgPool>,
+    registry: &State<Arc<MarketRegistry>>,
+    pool: &State<Arc<WalletPool>>,
+    leader: &State<Option<Arc<LeaderElection>>>,
+    health: &State<Arc<HealthState>>,
+) -> (Status, Json<ReadinessResponse>) {
+    let indexer_stale_secs = ev_or("HEALTH_INDEXER_STALE_SECS", 30.0);
+    let cycle_stale_secs = ev_or("HEALTH_CYCLE_STALE_SECS", 30.0);
+    let mut reasons = Vec::new();
+
+    let indexer_connected = health.indexer_connected();
+    if !indexer_connected {
+        reasons.push("indexer is not connected".to_string());
+    }
+    let secs_since_data_frame = health.secs_since_data_frame().await;
+    match secs_since_data_frame {
+        Some(secs) if secs > indexer_stale_secs => {
+            reasons.push(format!("no indexer data for {:.0}s", secs))
+        }
+        None => reasons.push("no indexer data received yet".to_string()),
+        _ => {}
+    }
+
+    // Followers do not run matching cycles, so only the leader is checked.
+    let is_leader = leader.as_ref().is_none_or(|l| l.is_leader());
+    let matcher_cycle_age_secs = health.cycle_ages().await;
+    if is_leader {
+        for market in registry.markets() {
+            match matcher_cycle_age_secs.get(&market.id) {
+                Some(age) if *age > cycle_stale_secs => reasons.push(format!(
+                    "matcher for {} has not run for {:.0}s",
+                    market.id, age
+                )),
+                None => reasons.push(format!("matcher for {} has not run yet", market.id)),
+                _ => {}
+            }
+        }
+    }
+
+    let database_connected = matches!(
+        tokio::time::timeout(
+            Duration::from_secs(2),
+            sqlx::query("SELECT 1").execute(&**db)
+        )
+        .await,
+        Ok(Ok(_))
+    );
+    if !database_connected {
+        reasons.push("database is unreachable".to_string());
+    }
+
+    let can_submit = pool.can_submit();
+    if !can_submit {
+        reasons.push("no matcher wallet is above WALLET_MIN_BALANCE".to_string());
+    }
+
+    let ready = reasons.is_empty();
+    let status = if ready {
+        Status::Ok
+    } else {
+        Status::ServiceUnavailable
+    };
+    (
+        status,
+        Json(ReadinessResponse {
+            ready,
+            reasons,
+            indexer_connected,
+            secs_since_data_frame,
+            matcher_cycle_age_secs,
+            last_transaction: health.last_transaction().await,
+            database_connected,
+            can_submit,
+            leader: is_leader,
+            wallets: pool.status(),
+        }),
+    )
+}
+
+#[get("/metrics")]
+async fn get_metrics(
+    registry: &State<Arc<MarketRegistry>>,
+    pool: &State<Arc<WalletPool>>,
+) -> (ContentType, String) {
+    (ContentType::Plain, metrics::render(registry, pool).await)
+}
+
+/// Server-sent events with book level changes, matches and transaction
+/// outcomes for one market.
+#[get("/markets/<market>/events")]
+async fn get_events(
+    registry: &State<Arc<MarketRegistry>>,
+    events: &State<Arc<EventBus>>,
+    market: &str,
+    mut end: Shutdown,
+) -> Option<EventStream![]> {
+    let market = registry.get(market)?.id.clone();
+    let mut receiver = events.subscribe();
+    Some(EventStream! {
+        loop {
+            let event = select! {
+                event = receiver.recv() => match event {
+                    Ok(event) => event,
+                    Err(RecvError::Closed) => break,
+                    Err(RecvError::Lagged(skipped)) => {
+                        yield Event::data(skipped.to_string()).event("lagged");
+                        continue;
+                    }
+                },
+                _ = &mut end => break,
+            };
+            if event.market() == market {
+                yield Event::json(&event).event(event.kind());
+            }
+        }
     })
 }
 
+/// Routes that are not part of the OpenAPI spec.
+pub fn get_plain_routes() -> Vec<Route> {
+    routes![get_metrics, get_events]
+}
+
 pub fn get_routes() -> Vec<Route> {
-    openapi_get_routes![get_stats, get_buy_orders, get_sell_orders, get_all_orders,]
+    openapi_get_routes![
+        get_stats,
+        get_transaction_history,
+        get_trade_history,
+        get_failed_transactions,
+        get_failure_stats,
+        get_markets,
+        get_market_info,
+        get_buy_orders,
+        get_sell_orders,
+        get_all_orders,
+        get_book_depth,
+        get_book_top,
+        get_wallets,
+        get_health_live,
+        get_health_ready,
+    ]
 }
 
 pub fn get_docs() -> SwaggerUIConfig {
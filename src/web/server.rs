@@ -4,21 +4,46 @@ use rocket::{Build, Rocket};
 use rocket_okapi::swagger_ui::make_swagger_ui;
 use sqlx::PgPool;
 
-use super::routes::{get_docs, get_routes};
-use crate::management::manager::OrderManager;
+use super::routes::{get_docs, get_plain_routes, get_routes};
+use crate::events::EventBus;
+use crate::health::HealthState;
+use crate::leader::LeaderElection;
+use crate::market::MarketRegistry;
+use crate::supervisor::Supervisor;
+use crate::wallet::WalletPool;
 
-pub fn rocket(db_pool: PgPool, order_manager: Arc<OrderManager>) -> Rocket<Build> {
+pub fn rocket(
+    db_pool: PgPool,
+    registry: Arc<MarketRegistry>,
+    wallet_pool: Arc<WalletPool>,
+    supervisor: Arc<Supervisor>,
+    leader: Option<Arc<LeaderElection>>,
+    health: Arc<HealthState>,
+    events: Arc<EventBus>,
+) -> Rocket<Build> {
     let port = crate::config::ev("ROCKET_PORT")
         .unwrap()
         .parse()
         .expect("Invalid port number");
 
+    // Signals are handled by the application so shutdown can be coordinated.
     rocket::custom(rocket::Config {
         port,
+        shutdown: rocket::config::Shutdown {
+            ctrlc: false,
+            signals: Default::default(),
+            ..Default::default()
+        },
         ..rocket::Config::default()
     })
     .manage(db_pool)
-    .manage(order_manager)
+    .manage(registry)
+    .manage(wallet_pool)
+    .manage(supervisor)
+    .manage(leader)
+    .manage(health)
+    .manage(events)
     .mount("/", get_routes())
+    .mount("/", get_plain_routes())
     .mount("/swagger", make_swagger_ui(&get_docs()))
 }
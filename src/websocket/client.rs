@@ -1,18 +1,23 @@
 use futures_util::{SinkExt, StreamExt};
 use log::{error, info};
-use serde_json::Value;
 use tokio::{
     net::TcpStream,
     sync::mpsc,
-    time::{self, Duration, Instant},
+    time::{Duration, Instant},
 };
 use tokio_tungstenite::{
     connect_async, tungstenite::protocol::Message, MaybeTlsStream, WebSocketStream,
 };
+use std::collections::HashSet;
+use std::sync::Arc;
 use url::Url;
 
 use crate::{
     api::subscription::format_graphql_subscription,
+    health::HealthState,
+    market::registry::{MarketContext, MarketRegistry},
+    metrics,
+    util::Shutdown,
     model::{
         spot_order::{SpotOrderIndexer, WebSocketResponse},
         OrderType, SpotOrder,
@@ -21,19 +26,27 @@ use crate::{
 
 pub struct WebSocketClient {
     pub url: Url,
+    pub registry: Arc<MarketRegistry>,
+    pub health: Arc<HealthState>,
 }
 
 impl WebSocketClient {
-    pub fn new(url: Url) -> Self {
-        WebSocketClient { url }
+    pub fn new(url: Url, registry: Arc<MarketRegistry>, health: Arc<HealthState>) -> Self {
+        WebSocketClient {
+            url,
+            registry,
+            health,
+        }
     }
 
     pub async fn connect(
         &self,
         sender: mpsc::Sender<SpotOrder>,
+        shutdown: Shutdown,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        loop {
+        while !shutdown.is_triggered() {
             let mut initialized = false;
+            metrics::WEBSOCKET_RECONNECTS.inc();
             let mut ws_stream = match self.connect_to_ws().await {
                 Ok(ws_stream) => ws_stream,
                 Err(e) => {
@@ -49,13 +62,20 @@ impl WebSocketClient {
                 .await
                 .expect("Failed to send init message");
 
-            self.subscribe_to_orders(OrderType::Buy, &mut ws_stream)
-                .await?;
-            self.subscribe_to_orders(OrderType::Sell, &mut ws_stream)
-                .await?;
+            self.subscribe_all(&mut ws_stream).await?;
 
             let mut last_data_time = Instant::now();
-            while let Some(message) = ws_stream.next().await {
+            loop {
+                let message = tokio::select! {
+                    message = ws_stream.next() => match message {
+                        Some(message) => message,
+                        None => break,
+                    },
+                    _ = shutdown.wait() => {
+                        info!("Shutdown requested, stopping order ingest");
+                        break;
+                    }
+                };
                 if Instant::now().duration_since(last_data_time) > Duration::from_secs(20) {
                     error!("No data messages received for the last 20 seconds, reconnecting...");
                     break;
@@ -71,30 +91,34 @@ impl WebSocketClient {
                                     continue;
                                 }
                                 "connection_ack" => {
+                                    self.health.set_indexer_connected(true);
                                     if !initialized {
                                         info!("Connection established, subscribing to orders...");
-                                        self.subscribe_to_orders(OrderType::Buy, &mut ws_stream)
-                                            .await?;
-                                        self.subscribe_to_orders(OrderType::Sell, &mut ws_stream)
-                                            .await?;
+                                        self.subscribe_all(&mut ws_stream).await?;
                                         initialized = true;
                                     }
                                 }
                                 "data" => {
+                                        let market = match response
+                                            .id
+                                            .as_deref()
+                                            .and_then(|id| self.market_for_subscription(id))
+                                        {
+                                            Some(market) => market,
+                                            None => {
+                                                error!("Data for unknown subscription id: {:?}", response.id);
+                                                continue;
+                                            }
+                                        };
                                         if let Some(payload) = response.payload {
                                             if let Some(orders) = payload.data.active_buy_order {
-                                                for order_indexer in orders {
-                                                    let spot_order = SpotOrder::from_indexer(order_indexer)?;
-                                                    sender.send(spot_order).await?;
-                                                }
+                                                self.forward_orders(orders, market, OrderType::Buy, &sender).await?;
                                             }
                                             if let Some(orders) = payload.data.active_sell_order {
-                                                for order_indexer in orders {
-                                                    let spot_order = SpotOrder::from_indexer(order_indexer)?;
-                                                    sender.send(spot_order).await?;
-                                                }
+                                                self.forward_orders(orders, market, OrderType::Sell, &sender).await?;
                                             }
                                             last_data_time = Instant::now();
+                                            self.health.record_data_frame().await;
                                         }
                                     }
                                 _ => {}
@@ -111,11 +135,101 @@ impl WebSocketClient {
                 }
             }
 
-            self.unsubscribe_orders(&mut ws_stream, OrderType::Buy)
+            self.health.set_indexer_connected(false);
+            self.unsubscribe_all(&mut ws_stream).await?;
+            if shutdown.is_triggered() {
+                let _ = ws_stream.close(None).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts indexer orders and passes them on; orders that fail to parse
+    /// or belong to another market or asset are counted and skipped. Restored
+    /// snapshot orders missing from the frame are dropped from the book.
+    async fn forward_orders(
+        &self,
+        orders: Vec<SpotOrderIndexer>,
+        market: &MarketContext,
+        side: OrderType,
+        sender: &mpsc::Sender<SpotOrder>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let seen: HashSet<String> = orders.iter().map(|o| o.id.clone()).collect();
+        market.order_manager.reconcile(side, &seen).await;
+        for order_indexer in orders {
+            let order_id = order_indexer.id.clone();
+            if let Some(reason) = market.foreign_order_reason(&order_indexer) {
+                metrics::ORDERS_REJECTED
+                    .with_label_values(&[&market.id, reason])
+                    .inc();
+                error!(
+                    "Rejected order {} from indexer for market {}: {} (market {:?}, asset {}, asset type {:?})",
+                    order_id,
+                    market.id,
+                    reason,
+                    order_indexer.market,
+                    order_indexer.asset,
+                    order_indexer.asset_type
+                );
+                continue;
+            }
+            // The parse error is not `Send`, so it must not live across the send below.
+            let spot_order = match SpotOrder::from_indexer(order_indexer, &market.id) {
+                Ok(spot_order) => spot_order,
+                Err(e) => {
+                    metrics::ORDERS_REJECTED
+                        .with_label_values(&[&market.id, "parse"])
+                        .inc();
+                    error!("Rejected order {} from indexer: {:?}", order_id, e);
+                    continue;
+                }
+            };
+            let side = match spot_order.order_type {
+                OrderType::Buy => "buy",
+                OrderType::Sell => "sell",
+            };
+            metrics::ORDERS_INGESTED
+                .with_label_values(&[&market.id, side])
+                .inc();
+            sender.send(spot_order).await?;
+        }
+        Ok(())
+    }
+
+    async fn subscribe_all(
+        &self,
+        client: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for market in self.registry.markets() {
+            self.subscribe_to_orders(market, OrderType::Buy, client)
+                .await?;
+            self.subscribe_to_orders(market, OrderType::Sell, client)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn unsubscribe_all(
+        &self,
+        client: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for market in self.registry.markets() {
+            self.unsubscribe_orders(client, market, OrderType::Buy)
                 .await?;
-            self.unsubscribe_orders(&mut ws_stream, OrderType::Sell)
+            self.unsubscribe_orders(client, market, OrderType::Sell)
                 .await?;
         }
+        Ok(())
+    }
+
+    /// Subscription ids are `<market index>-<order type>`.
+    fn subscription_id(market: &MarketContext, order_type: OrderType) -> String {
+        format!("{}-{}", market.index, order_type as u8)
+    }
+
+    fn market_for_subscription(&self, id: &str) -> Option<&Arc<MarketContext>> {
+        let (index, _) = id.split_once('-')?;
+        self.registry.by_index(index.parse().ok()?)
     }
 
     async fn connect_to_ws(
@@ -138,12 +252,13 @@ impl WebSocketClient {
 
     async fn subscribe_to_orders(
         &self,
+        market: &MarketContext,
         order_type: OrderType,
         client: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let subscription_query = format_graphql_subscription(order_type);
+        let subscription_query = format_graphql_subscription(order_type, &market.id);
         let start_msg = serde_json::json!({
-            "id": format!("{}", order_type as u8),
+            "id": Self::subscription_id(market, order_type),
             "type": "start",
             "payload": {
                 "query": subscription_query
@@ -160,10 +275,11 @@ impl WebSocketClient {
     async fn unsubscribe_orders(
         &self,
         client: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+        market: &MarketContext,
         order_type: OrderType,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let stop_msg = serde_json::json!({
-            "id": format!("{}", order_type as u8),
+            "id": Self::subscription_id(market, order_type),
             "type": "stop"
         })
         .to_string();